@@ -0,0 +1 @@
+DROP TABLE rated_slots;
\ No newline at end of file
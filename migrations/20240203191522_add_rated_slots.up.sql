@@ -0,0 +1,8 @@
+CREATE TABLE rated_slots (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    slot_id bigint NOT NULL REFERENCES slots ON DELETE CASCADE,
+    rating smallint CHECK (rating >= -1 AND rating <= 1),
+    lbp1_rating smallint CHECK (lbp1_rating >= 1 AND lbp1_rating <= 5),
+    timestamp timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, slot_id)
+);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE played_slots;
\ No newline at end of file
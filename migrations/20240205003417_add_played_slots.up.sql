@@ -0,0 +1,10 @@
+CREATE TABLE played_slots (
+    id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL,
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    slot_id bigint NOT NULL REFERENCES slots ON DELETE CASCADE,
+    gamever smallint NOT NULL,
+    completed bool DEFAULT FALSE NOT NULL,
+    timestamp timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX played_slots_slot_id_idx ON played_slots (slot_id, gamever);
\ No newline at end of file
@@ -0,0 +1,7 @@
+DELETE FROM slots WHERE story_id IS NOT NULL;
+
+ALTER TABLE slots
+    DROP CONSTRAINT slots_story_id_check,
+    ALTER COLUMN root_level SET NOT NULL,
+    ALTER COLUMN author SET NOT NULL,
+    DROP COLUMN story_id;
\ No newline at end of file
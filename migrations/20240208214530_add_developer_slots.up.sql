@@ -0,0 +1,6 @@
+ALTER TABLE slots
+    ADD COLUMN story_id bigint UNIQUE,
+    ALTER COLUMN author DROP NOT NULL,
+    ALTER COLUMN root_level DROP NOT NULL,
+    ADD CONSTRAINT slots_story_id_check
+        CHECK ((story_id IS NULL) = (author IS NOT NULL AND root_level IS NOT NULL));
\ No newline at end of file
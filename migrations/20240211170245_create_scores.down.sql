@@ -0,0 +1 @@
+DROP TABLE scores;
\ No newline at end of file
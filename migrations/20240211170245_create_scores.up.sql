@@ -0,0 +1,10 @@
+CREATE TABLE scores (
+    id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL,
+    slot_id bigint NOT NULL REFERENCES slots ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    player_count smallint NOT NULL CHECK (player_count >= 1 AND player_count <= 4),
+    players varchar(16)[] NOT NULL CHECK (cardinality(players) = player_count),
+    score integer NOT NULL,
+    submitted_at timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    UNIQUE (slot_id, player_count, players)
+);
\ No newline at end of file
@@ -0,0 +1,2 @@
+DROP TABLE photo_subjects;
+DROP TABLE photos;
\ No newline at end of file
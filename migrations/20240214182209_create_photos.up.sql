@@ -0,0 +1,18 @@
+CREATE TABLE photos (
+    id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL,
+    author uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    slot_id bigint REFERENCES slots ON DELETE SET NULL,
+    small varchar(40) NOT NULL,
+    medium varchar(40) NOT NULL,
+    large varchar(40) NOT NULL,
+    plan varchar(40) NOT NULL,
+    taken_at timestamp NOT NULL,
+    uploaded_at timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE TABLE photo_subjects (
+    photo_id bigint NOT NULL REFERENCES photos ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    bounds varchar NOT NULL,
+    PRIMARY KEY (photo_id, user_id)
+);
\ No newline at end of file
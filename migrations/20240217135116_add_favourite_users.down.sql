@@ -0,0 +1 @@
+DROP TABLE favourite_users;
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE favourite_users (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    target_id uuid NOT NULL REFERENCES users ON DELETE CASCADE CHECK (target_id != user_id),
+    timestamp timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, target_id)
+);
\ No newline at end of file
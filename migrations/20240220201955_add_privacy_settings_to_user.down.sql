@@ -0,0 +1,3 @@
+ALTER TABLE users
+    DROP COLUMN level_visibility,
+    DROP COLUMN profile_visibility;
\ No newline at end of file
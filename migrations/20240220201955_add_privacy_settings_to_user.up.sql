@@ -0,0 +1,5 @@
+ALTER TABLE users
+    ADD COLUMN level_visibility varchar DEFAULT 'all' NOT NULL
+        CHECK (level_visibility IN ('all', 'psn', 'myself')),
+    ADD COLUMN profile_visibility varchar DEFAULT 'all' NOT NULL
+        CHECK (profile_visibility IN ('all', 'psn', 'myself'));
\ No newline at end of file
@@ -0,0 +1 @@
+DROP INDEX slots_search_idx;
\ No newline at end of file
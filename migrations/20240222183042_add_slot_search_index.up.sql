@@ -0,0 +1 @@
+CREATE INDEX slots_search_idx ON slots USING GIN (to_tsvector('simple', name || ' ' || description));
\ No newline at end of file
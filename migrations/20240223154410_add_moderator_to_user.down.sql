@@ -0,0 +1 @@
+ALTER TABLE users DROP COLUMN is_moderator;
\ No newline at end of file
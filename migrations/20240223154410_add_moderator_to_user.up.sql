@@ -0,0 +1 @@
+ALTER TABLE users ADD COLUMN is_moderator bool DEFAULT FALSE NOT NULL;
\ No newline at end of file
@@ -0,0 +1,3 @@
+DROP TABLE favourite_playlists;
+DROP TABLE playlist_slots;
+DROP TABLE playlists;
\ No newline at end of file
@@ -0,0 +1,22 @@
+CREATE TABLE playlists (
+    id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL,
+    author uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    name varchar(64) NOT NULL,
+    description varchar(512) DEFAULT '' NOT NULL,
+    icon varchar(40) DEFAULT '' NOT NULL,
+    created_at timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE TABLE playlist_slots (
+    playlist_id bigint NOT NULL REFERENCES playlists ON DELETE CASCADE,
+    slot_id bigint NOT NULL REFERENCES slots ON DELETE CASCADE,
+    position integer NOT NULL,
+    PRIMARY KEY (playlist_id, slot_id)
+);
+
+CREATE TABLE favourite_playlists (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    playlist_id bigint NOT NULL REFERENCES playlists ON DELETE CASCADE,
+    timestamp timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, playlist_id)
+);
\ No newline at end of file
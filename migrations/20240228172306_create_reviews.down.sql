@@ -0,0 +1,2 @@
+DROP TABLE rated_reviews;
+DROP TABLE reviews;
\ No newline at end of file
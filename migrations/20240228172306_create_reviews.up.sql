@@ -0,0 +1,17 @@
+CREATE TABLE reviews (
+    id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL,
+    slot_id bigint NOT NULL REFERENCES slots ON DELETE CASCADE,
+    author uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    content varchar(512) DEFAULT '' NOT NULL,
+    labels varchar[] DEFAULT '{}' NOT NULL,
+    posted_at timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    UNIQUE (slot_id, author)
+);
+
+CREATE TABLE rated_reviews (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    review_id bigint NOT NULL REFERENCES reviews ON DELETE CASCADE,
+    rating smallint NOT NULL CHECK (rating >= -1 AND rating <= 1),
+    timestamp timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, review_id)
+);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE rated_comments;
\ No newline at end of file
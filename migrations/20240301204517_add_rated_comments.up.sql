@@ -0,0 +1,7 @@
+CREATE TABLE rated_comments (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    comment_id bigint NOT NULL REFERENCES comments ON DELETE CASCADE,
+    rating smallint NOT NULL CHECK (rating >= -1 AND rating <= 1),
+    timestamp timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, comment_id)
+);
\ No newline at end of file
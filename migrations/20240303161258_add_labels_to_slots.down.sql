@@ -0,0 +1 @@
+ALTER TABLE slots DROP COLUMN labels;
\ No newline at end of file
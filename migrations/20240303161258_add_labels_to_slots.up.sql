@@ -0,0 +1 @@
+ALTER TABLE slots ADD COLUMN labels varchar[] DEFAULT '{}' NOT NULL;
\ No newline at end of file
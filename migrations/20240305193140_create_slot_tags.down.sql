@@ -0,0 +1 @@
+DROP TABLE slot_tags;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE slot_tags (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    slot_id bigint NOT NULL REFERENCES slots ON DELETE CASCADE,
+    tag varchar NOT NULL,
+    timestamp timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, slot_id)
+);
+
+CREATE INDEX slot_tags_slot_id_idx ON slot_tags (slot_id, tag);
\ No newline at end of file
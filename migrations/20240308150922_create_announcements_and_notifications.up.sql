@@ -0,0 +1,20 @@
+CREATE TABLE announcements (
+    id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL,
+    author uuid REFERENCES users ON DELETE SET NULL,
+    title varchar(128) NOT NULL,
+    content varchar NOT NULL,
+    game_versions smallint[] DEFAULT '{}' NOT NULL,
+    starts_at timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    ends_at timestamp CHECK (ends_at > starts_at),
+    created_at timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE TABLE notifications (
+    id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL,
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    content varchar NOT NULL,
+    delivered bool DEFAULT FALSE NOT NULL,
+    created_at timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX notifications_user_id_idx ON notifications (user_id) WHERE NOT delivered;
\ No newline at end of file
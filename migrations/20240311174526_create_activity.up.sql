@@ -0,0 +1,13 @@
+CREATE TABLE activity (
+    id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL,
+    actor uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    event_type varchar(32) NOT NULL,
+    slot_id bigint REFERENCES slots ON DELETE CASCADE,
+    target_user uuid REFERENCES users ON DELETE CASCADE,
+    comment_id bigint REFERENCES comments ON DELETE CASCADE,
+    photo_id bigint REFERENCES photos ON DELETE CASCADE,
+    timestamp timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX activity_timestamp_idx ON activity (timestamp);
+CREATE INDEX activity_slot_id_idx ON activity (slot_id, timestamp);
\ No newline at end of file
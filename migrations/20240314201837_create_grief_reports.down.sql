@@ -0,0 +1 @@
+DROP TABLE grief_reports;
\ No newline at end of file
@@ -0,0 +1,21 @@
+CREATE TABLE grief_reports (
+    id bigint GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL,
+    reporter uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    grief_type int NOT NULL,
+    slot_id bigint REFERENCES slots ON DELETE SET NULL,
+    level_type varchar NOT NULL,
+    level_owner varchar,
+    description varchar DEFAULT '' NOT NULL,
+    jpeg_hash varchar(40),
+    initial_state_hash varchar(40),
+    grief_state_hash varchar(40),
+    players varchar[] DEFAULT '{}' NOT NULL,
+    status varchar DEFAULT 'open' NOT NULL
+        CHECK (status IN ('open', 'resolved', 'dismissed')),
+    resolved_by uuid REFERENCES users ON DELETE SET NULL,
+    resolved_at timestamp,
+    reported_at timestamp DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX grief_reports_open_idx ON grief_reports (reported_at) WHERE status = 'open';
+CREATE INDEX grief_reports_slot_id_idx ON grief_reports (slot_id);
\ No newline at end of file
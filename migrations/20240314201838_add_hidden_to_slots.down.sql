@@ -0,0 +1 @@
+ALTER TABLE slots DROP COLUMN hidden;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE slots ADD COLUMN hidden bool DEFAULT FALSE NOT NULL;
\ No newline at end of file
@@ -0,0 +1,2 @@
+DROP INDEX users_online_id_lower_idx;
+DROP INDEX slots_author_idx;
\ No newline at end of file
@@ -0,0 +1,2 @@
+CREATE INDEX slots_author_idx ON slots (author);
+CREATE INDEX users_online_id_lower_idx ON users (lower(online_id));
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE slots DROP COLUMN auto_hidden;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE slots ADD COLUMN auto_hidden bool DEFAULT FALSE NOT NULL;
\ No newline at end of file
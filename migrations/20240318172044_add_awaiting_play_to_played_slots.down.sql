@@ -0,0 +1 @@
+ALTER TABLE played_slots DROP COLUMN awaiting_play;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE played_slots ADD COLUMN awaiting_play bool DEFAULT FALSE NOT NULL;
\ No newline at end of file
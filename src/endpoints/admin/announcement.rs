@@ -0,0 +1,116 @@
+use axum::{Router, routing::post, extract::{State, Path}, response::{IntoResponse, Response}};
+use chrono::DateTime;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    extractors::Json,
+    types::GameVersion,
+    utils::db::{db_error, get_id_from_username, notify},
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/announcements", post(create_announcement))
+        .route("/announcements/:id/delete", post(delete_announcement))
+        .route("/notify/:online_id", post(notify_user))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnnouncementPayload {
+    title: String,
+    content: String,
+    // e.g. "lbp2", announcements without any are shown in every game
+    #[serde(default)]
+    game_version: Vec<String>,
+    // unix timestamps in milliseconds
+    starts_at: Option<i64>,
+    ends_at: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct CreatedAnnouncement {
+    id: i64,
+}
+
+async fn create_announcement(
+    State(state): State<AppState>,
+    payload: Json<AnnouncementPayload>,
+) -> Result<impl IntoResponse, Response> {
+    let mut game_versions = Vec::new();
+    for name in &payload.game_version {
+        let game_version = [GameVersion::Lbp1, GameVersion::Lbp2, GameVersion::Lbp3].into_iter()
+            .find(|v| v.to_string() == *name)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid game version").into_response())?;
+        game_versions.push(game_version as i16);
+    }
+
+    let mut timestamps = Vec::new();
+    for timestamp in [payload.starts_at, payload.ends_at] {
+        timestamps.push(match timestamp {
+            Some(ts) => Some(
+                DateTime::from_timestamp_millis(ts)
+                    .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid timestamp").into_response())?
+                    .naive_utc()
+            ),
+            None => None,
+        });
+    }
+    if let [Some(starts_at), Some(ends_at)] = timestamps[..] {
+        if ends_at <= starts_at {
+            return Err((StatusCode::BAD_REQUEST, "Announcement ends before it starts").into_response());
+        }
+    }
+
+    let id = sqlx::query!(
+        "INSERT INTO announcements (title, content, game_versions, starts_at, ends_at)
+        VALUES ($1, $2, $3, COALESCE($4::timestamp, CURRENT_TIMESTAMP), $5)
+        RETURNING id",
+        payload.title,
+        payload.content,
+        &game_versions,
+        timestamps[0],
+        timestamps[1],
+    )
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?
+        .id;
+
+    Ok(Json(CreatedAnnouncement { id }))
+}
+
+async fn delete_announcement(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, Response> {
+    let deleted = sqlx::query!("DELETE FROM announcements WHERE id = $1", id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+
+    if deleted == 0 {
+        return Err((StatusCode::NOT_FOUND, "Announcement not found").into_response());
+    }
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+struct NotifyPayload {
+    text: String,
+}
+
+async fn notify_user(
+    State(state): State<AppState>,
+    Path(online_id): Path<String>,
+    payload: Json<NotifyPayload>,
+) -> Result<impl IntoResponse, Response> {
+    let user_id = get_id_from_username(&online_id, &state).await?;
+    notify(user_id, &payload.text, &state).await?;
+
+    Ok(StatusCode::OK)
+}
\ No newline at end of file
@@ -0,0 +1,14 @@
+use axum::{Router, middleware::from_fn_with_state};
+
+use crate::{AppState, middleware, types::Config};
+
+mod announcement;
+mod moderation;
+
+// tools for server operators, kept out of the gameserver since the game never calls these
+pub fn routes(config: &Config) -> Router<AppState> {
+    Router::new()
+        .merge(announcement::routes())
+        .merge(moderation::routes())
+        .layer(from_fn_with_state(config.admin_token.clone(), middleware::verify_admin_token))
+}
\ No newline at end of file
@@ -0,0 +1,222 @@
+use axum::{Router, routing::{get, post}, extract::{State, Path}, response::{IntoResponse, Response}};
+use axum_extra::extract::Query;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    extractors::Json,
+    utils::db::{auto_unhide_slot, check_slot, db_error, notify},
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/griefReports", get(grief_reports))
+        .route("/griefReports/:id/resolve", post(resolve_grief_report))
+        .route("/griefReports/:id/dismiss", post(dismiss_grief_report))
+        .route("/slots/:id/hide", post(hide_slot))
+        .route("/slots/:id/unhide", post(unhide_slot))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GriefReportListQuery {
+    page_start: i64,
+    page_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GriefReport {
+    id: i64,
+    grief_type_id: i32,
+    reporter: String,
+    // row id, developer slots also get their story id
+    slot_id: Option<i64>,
+    story_id: Option<i64>,
+    level_type: String,
+    level_owner: Option<String>,
+    description: String,
+    jpeg_hash: Option<String>,
+    initial_state_hash: Option<String>,
+    grief_state_hash: Option<String>,
+    players: Vec<String>,
+    timestamp: i64,
+}
+
+#[derive(Serialize)]
+struct GriefReportList {
+    total: i64,
+    reports: Vec<GriefReport>,
+}
+
+// open reports, oldest first
+async fn grief_reports(
+    query: Query<GriefReportListQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    let reports = sqlx::query!(
+        "SELECT grief_reports.*, reporter.online_id AS reporter_oid,
+        slots.story_id AS \"story_id?\",
+        COUNT(*) OVER() AS total
+        FROM grief_reports
+        JOIN users reporter ON grief_reports.reporter = reporter.id
+        LEFT JOIN slots ON grief_reports.slot_id = slots.id
+        WHERE grief_reports.status = 'open'
+        ORDER BY grief_reports.reported_at
+        LIMIT $1 OFFSET $2",
+        query.page_size,
+        query.page_start - 1,
+    )
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    let total = reports.first().map_or(0, |r| r.total.unwrap_or_default());
+
+    Ok(Json(GriefReportList {
+        total,
+        reports: reports.into_iter()
+            .map(|report| GriefReport {
+                id: report.id,
+                grief_type_id: report.grief_type,
+                reporter: report.reporter_oid,
+                slot_id: report.slot_id,
+                story_id: report.story_id,
+                level_type: report.level_type,
+                level_owner: report.level_owner,
+                description: report.description,
+                jpeg_hash: report.jpeg_hash,
+                initial_state_hash: report.initial_state_hash,
+                grief_state_hash: report.grief_state_hash,
+                players: report.players,
+                timestamp: report.reported_at.and_utc().timestamp_millis(),
+            })
+            .collect(),
+    }))
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum GriefReportAction {
+    HideSlot,
+    UnpublishSlot,
+}
+
+#[derive(Deserialize)]
+struct ResolveGriefReportQuery {
+    action: Option<GriefReportAction>,
+}
+
+async fn resolve_grief_report(
+    Path(id): Path<i64>,
+    query: Query<ResolveGriefReportQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    let report = sqlx::query!("SELECT slot_id FROM grief_reports WHERE id = $1 AND status = 'open'", id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Report not found").into_response())?;
+
+    let action_slot = match query.action {
+        Some(_) => {
+            let slot_id = report.slot_id
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, "Report isn't about a slot").into_response())?;
+            check_slot(slot_id, &state).await?;
+            Some(slot_id)
+        },
+        None => None,
+    };
+
+    // acting on a slot takes care of every other report about it too
+    let reporters = sqlx::query!(
+        "UPDATE grief_reports SET status = 'resolved', resolved_at = CURRENT_TIMESTAMP
+        WHERE status = 'open' AND (id = $1 OR slot_id = $2)
+        RETURNING reporter",
+        id,
+        action_slot,
+    )
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    match (query.action, action_slot) {
+        (Some(GriefReportAction::HideSlot), Some(slot_id)) => {
+            sqlx::query!("UPDATE slots SET hidden = TRUE, auto_hidden = FALSE WHERE id = $1", slot_id)
+                .execute(&state.pool)
+                .await
+                .map_err(db_error)?;
+        },
+        (Some(GriefReportAction::UnpublishSlot), Some(slot_id)) => {
+            sqlx::query!("DELETE FROM slots WHERE id = $1", slot_id)
+                .execute(&state.pool)
+                .await
+                .map_err(db_error)?;
+        },
+        _ => {
+            if let Some(slot_id) = report.slot_id {
+                auto_unhide_slot(&state.pool, slot_id, state.config.grief_report_threshold).await.map_err(db_error)?;
+            }
+        },
+    }
+
+    let mut reporters: Vec<_> = reporters.into_iter().map(|r| r.reporter).collect();
+    reporters.sort();
+    reporters.dedup();
+    for reporter in reporters {
+        notify(reporter, "A moderator has looked into your grief report. Thank you!", &state).await?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+async fn dismiss_grief_report(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    let report = sqlx::query!(
+        "UPDATE grief_reports SET status = 'dismissed', resolved_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status = 'open'
+        RETURNING slot_id",
+        id,
+    )
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Report not found").into_response())?;
+
+    if let Some(slot_id) = report.slot_id {
+        auto_unhide_slot(&state.pool, slot_id, state.config.grief_report_threshold).await.map_err(db_error)?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+async fn hide_slot(
+    State(state): State<AppState>,
+    Path(slot_id): Path<i64>,
+) -> Result<impl IntoResponse, Response> {
+    check_slot(slot_id, &state).await?;
+
+    sqlx::query!("UPDATE slots SET hidden = TRUE, auto_hidden = FALSE WHERE id = $1", slot_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn unhide_slot(
+    State(state): State<AppState>,
+    Path(slot_id): Path<i64>,
+) -> Result<impl IntoResponse, Response> {
+    check_slot(slot_id, &state).await?;
+
+    sqlx::query!("UPDATE slots SET hidden = FALSE, auto_hidden = FALSE WHERE id = $1", slot_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
\ No newline at end of file
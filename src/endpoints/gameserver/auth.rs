@@ -2,6 +2,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use axum::{extract::State, body::Bytes, response::{IntoResponse, Response}, http::StatusCode};
 use maud::html as xml;
+use serde::Deserialize;
 use sqlx::types::BigDecimal;
 use tower_sessions::Session;
 use uuid::Uuid;
@@ -69,6 +70,25 @@ pub async fn goodbye(session: Session) -> impl IntoResponse {
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())
 }
 
+#[derive(Deserialize)]
+struct NpHandleList {
+    #[serde(default, rename = "npHandle")]
+    np_handles: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NpDataPayload {
+    friends: Option<NpHandleList>,
+}
+
+// lbp2+ sends the user's psn friend list after logging in
+pub async fn npdata(session: Session, payload: Xml<NpDataPayload>) -> impl IntoResponse {
+    let friends = payload.0.friends.map(|f| f.np_handles).unwrap_or_default();
+    session.insert("friends", friends)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())
+}
+
 struct UserData {
     id: Uuid,
     online_id: String,
@@ -1,14 +1,14 @@
 use axum::{Router, routing::{get, post}, extract::{Path, State}, http::StatusCode, response::{IntoResponse, Response}, Extension};
 use axum_extra::extract::Query;
 use futures::TryStreamExt;
-use maud::html as xml;
+use maud::{html as xml, Markup};
 use serde::Deserialize;
 use sqlx::QueryBuilder;
 use sqlx::types::chrono::NaiveDateTime;
 
-use crate::{extractors::Xml, types::SessionData, AppState, utils::db::{db_error, check_slot, get_id_from_username}};
+use crate::{extractors::Xml, types::{Activity, SessionData}, AppState, utils::db::{db_error, get_id_from_username, check_slot_visibility, check_user_visibility, record_activity}};
 use crate::endpoints::gameserver::comment::CommentTarget::{Slot, User};
-use crate::endpoints::gameserver::SlotType;
+use crate::endpoints::gameserver::{find_slot_id, get_slot_id, SlotType};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
@@ -18,6 +18,8 @@ pub fn routes() -> Router<AppState> {
         .route("/userComments/:online_id", get(user_comments))
         .route("/postUserComment/:online_id", post(post_user_comment))
         .route("/deleteUserComment/:online_id", post(delete_comment))
+        .route("/rateComment/:slot_type/:slot_id", post(rate_slot_comment))
+        .route("/rateUserComment/:online_id", post(rate_user_comment))
 }
 
 #[derive(Deserialize)]
@@ -29,7 +31,7 @@ struct CommentListQuery {
 
 enum CommentTarget {
     User(String),
-    Slot(SlotType, i64),
+    Slot(i64),
 }
 
 #[derive(sqlx::FromRow)]
@@ -40,17 +42,25 @@ struct Comment {
     deleted_by_mod: bool,
     author_oid: String,
     deleter_oid: Option<String>,
+    thumbsup: i64,
+    thumbsdown: i64,
+    your_thumb: i16,
 }
 
 async fn slot_comments(
-    // TODO: use slot type
-    Path((slot_type, slot_id)): Path<(SlotType, i64)>,
+    Path((slot_type, id)): Path<(SlotType, i64)>,
     query: Query<CommentListQuery>,
     State(state): State<AppState>,
+    session: Extension<SessionData>,
 ) -> Result<impl IntoResponse, Response> {
+    let Some(slot_id) = find_slot_id(slot_type, id, &state).await? else {
+        return Ok(Xml(xml!(comments {})));
+    };
+    check_slot_visibility(slot_id, &session, &state).await?;
     comments(
-        CommentTarget::Slot(slot_type, slot_id),
+        CommentTarget::Slot(slot_id),
         query,
+        session,
         state,
     ).await
 }
@@ -59,10 +69,14 @@ async fn user_comments(
     Path(online_id): Path<String>,
     query: Query<CommentListQuery>,
     State(state): State<AppState>,
+    session: Extension<SessionData>,
 ) -> Result<impl IntoResponse, Response> {
+    let user_id = get_id_from_username(&online_id, &state).await?;
+    check_user_visibility(user_id, &session, &state).await?;
     comments(
         CommentTarget::User(online_id),
         query,
+        session,
         state,
     ).await
 }
@@ -70,14 +84,22 @@ async fn user_comments(
 async fn comments(
     target: CommentTarget,
     params: Query<CommentListQuery>,
+    session: Extension<SessionData>,
     state: AppState,
-) -> Result<impl IntoResponse, Response> {
+) -> Result<Xml<Markup>, Response> {
     // what the fuck have i done
     let mut query = QueryBuilder::new(
         "SELECT comm.id, comm.posted_at, comm.content, comm.deleted_by_mod,
         author.online_id AS author_oid,
-        deleter.online_id AS deleter_oid
-        FROM comments comm
+        deleter.online_id AS deleter_oid,
+        (SELECT COUNT(*) FROM rated_comments WHERE comment_id = comm.id AND rating = 1) AS thumbsup,
+        (SELECT COUNT(*) FROM rated_comments WHERE comment_id = comm.id AND rating = -1) AS thumbsdown,"
+    );
+    query.push(" COALESCE((SELECT rating FROM rated_comments WHERE comment_id = comm.id AND user_id = ");
+    query.push_bind(session.user_id);
+    query.push("), 0)::int2 AS your_thumb");
+    query.push(
+        " FROM comments comm
         JOIN users author ON comm.author = author.id"
     );
     if let CommentTarget::User(_) = target {
@@ -85,7 +107,7 @@ async fn comments(
     }
     query.push(" LEFT JOIN users AS deleter ON comm.deleted_by = deleter.id");
     match target {
-        CommentTarget::Slot(_, id) => {
+        CommentTarget::Slot(id) => {
             query.push(" WHERE target_slot = ");
             query.push_bind(id);
         }
@@ -122,9 +144,9 @@ async fn comments(
                     } @else {
                         message { (comment.content) }
                     }
-                    thumbsup { "0" } // TODO: fix this once ratings are implemented
-                    thumbsdown { "0" }
-                    yourthumb { "0" }
+                    thumbsup { (comment.thumbsup) }
+                    thumbsdown { (comment.thumbsdown) }
+                    yourthumb { (comment.your_thumb) }
                 }
             }
         }
@@ -137,14 +159,15 @@ struct PostCommentPayload {
 }
 
 async fn post_slot_comment(
-    // TODO: use slot type
-    Path((slot_type, slot_id)): Path<(SlotType, i64)>,
+    Path((slot_type, id)): Path<(SlotType, i64)>,
     State(state): State<AppState>,
     session: Extension<SessionData>,
     payload: Xml<PostCommentPayload>,
 ) -> Result<impl IntoResponse, Response> {
+    let slot_id = get_slot_id(slot_type, id, &session, &state).await?;
+    check_slot_visibility(slot_id, &session, &state).await?;
     post_comment(
-        CommentTarget::Slot(slot_type, slot_id),
+        CommentTarget::Slot(slot_id),
         state,
         session,
         payload,
@@ -166,37 +189,50 @@ async fn post_user_comment(
 }
 
 async fn post_comment(
-    // TODO: use slot type
     target: CommentTarget,
     state: AppState,
     session: Extension<SessionData>,
     payload: Xml<PostCommentPayload>,
 ) -> Result<impl IntoResponse, Response> {
     let user_id = match target {
-        CommentTarget::Slot(_, id) => {
-            check_slot(id, &state).await?;
-            None
+        CommentTarget::Slot(_) => None,
+        CommentTarget::User(ref username) => {
+            let user_id = get_id_from_username(username, &state).await?;
+            check_user_visibility(user_id, &session, &state).await?;
+            Some(user_id)
+        }
+    };
+
+    let activity = match target {
+        CommentTarget::Slot(slot_id) => Activity::CommentOnLevel {
+            slot_id,
+            comment_id: sqlx::query!(
+                "INSERT INTO comments (author, target_slot, content) VALUES ($1, $2, $3) RETURNING id",
+                session.user_id,
+                slot_id,
+                state.text_filter.censor(&payload.message)
+            )
+                .fetch_one(&state.pool)
+                .await
+                .map_err(db_error)?
+                .id,
+        },
+        CommentTarget::User(_) => Activity::CommentOnUser {
+            user_id: user_id.unwrap(),
+            comment_id: sqlx::query!(
+                "INSERT INTO comments (author, target_user, content) VALUES ($1, $2, $3) RETURNING id",
+                session.user_id,
+                user_id.unwrap(),
+                state.text_filter.censor(&payload.message)
+            )
+                .fetch_one(&state.pool)
+                .await
+                .map_err(db_error)?
+                .id,
         },
-        CommentTarget::User(ref username) => Some(get_id_from_username(username, &state).await?)
     };
 
-    match target {
-        CommentTarget::Slot(_, id) => sqlx::query!(
-            "INSERT INTO comments (author, target_slot, content) VALUES ($1, $2, $3)",
-            session.user_id,
-            id,
-            payload.message
-        ),
-        CommentTarget::User(_) => sqlx::query!(
-            "INSERT INTO comments (author, target_user, content) VALUES ($1, $2, $3)",
-            session.user_id,
-            user_id.unwrap(),
-            payload.message
-        )
-    }
-        .execute(&state.pool)
-        .await
-        .map_err(db_error)?;
+    record_activity(session.user_id, activity, &state).await?;
 
     Ok(StatusCode::OK)
 }
@@ -224,7 +260,7 @@ async fn delete_comment(
         .ok_or_else(|| (StatusCode::NOT_FOUND, "Comment not found").into_response())?;
 
     let target = if target_refs.slot.unwrap() {
-        Slot(SlotType::User, 0)
+        Slot(0)
     } else if target_refs.user.unwrap() {
         User(String::new())
     } else {
@@ -247,8 +283,8 @@ async fn delete_comment(
     }
 
     let is_allowed = match target {
-        CommentTarget::Slot(_, _) => sqlx::query!(
-            "SELECT comments.author = $2 OR slots.author = $2 AS is_allowed
+        CommentTarget::Slot(_) => sqlx::query!(
+            "SELECT comments.author = $2 OR COALESCE(slots.author = $2, FALSE) AS is_allowed
             FROM comments JOIN slots ON target_slot = slots.id
             WHERE comments.id = $1",
             query.comment_id,
@@ -289,3 +325,98 @@ async fn delete_comment(
 
     Ok(StatusCode::OK)
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommentRateQuery {
+    comment_id: i64,
+    rating: i16,
+}
+
+async fn rate_slot_comment(
+    Path((slot_type, id)): Path<(SlotType, i64)>,
+    query: Query<CommentRateQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    // a developer slot without a row can't have any comments to rate
+    let slot_id = find_slot_id(slot_type, id, &state).await?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Comment not found").into_response())?;
+    check_slot_visibility(slot_id, &session, &state).await?;
+    rate_comment(
+        CommentTarget::Slot(slot_id),
+        query,
+        state,
+        session,
+    ).await
+}
+
+async fn rate_user_comment(
+    Path(online_id): Path<String>,
+    query: Query<CommentRateQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let user_id = get_id_from_username(&online_id, &state).await?;
+    check_user_visibility(user_id, &session, &state).await?;
+    rate_comment(
+        CommentTarget::User(online_id),
+        query,
+        state,
+        session,
+    ).await
+}
+
+async fn rate_comment(
+    target: CommentTarget,
+    query: Query<CommentRateQuery>,
+    state: AppState,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    if !(-1..=1).contains(&query.rating) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid rating").into_response());
+    }
+
+    // the comment has to actually be on the target from the url
+    let comment = match target {
+        CommentTarget::Slot(id) => sqlx::query!(
+            "SELECT author FROM comments WHERE id = $1 AND target_slot = $2",
+            query.comment_id,
+            id,
+        )
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)?
+            .map(|c| c.author),
+        CommentTarget::User(username) => sqlx::query!(
+            "SELECT author FROM comments
+            JOIN users target_user ON comments.target_user = target_user.id
+            WHERE comments.id = $1 AND target_user.online_id = $2",
+            query.comment_id,
+            username,
+        )
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)?
+            .map(|c| c.author),
+    };
+
+    let author = comment.ok_or_else(|| (StatusCode::NOT_FOUND, "Comment not found").into_response())?;
+    if author == session.user_id {
+        return Err((StatusCode::BAD_REQUEST, "Cannot rate your own comment").into_response());
+    }
+
+    sqlx::query!(
+        "INSERT INTO rated_comments (user_id, comment_id, rating) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, comment_id) DO UPDATE
+        SET rating = EXCLUDED.rating, timestamp = CURRENT_TIMESTAMP",
+        session.user_id,
+        query.comment_id,
+        query.rating,
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
@@ -1,4 +1,4 @@
-use axum::{Router, routing::post};
+use axum::{Router, routing::post, extract::State};
 
 use crate::AppState;
 
@@ -7,7 +7,6 @@ pub fn routes() -> Router<AppState> {
         .route("/filter", post(filter))
 }
 
-// TODO: yet another stubbaroni
-async fn filter(str: String) -> String {
-    str
+async fn filter(State(state): State<AppState>, str: String) -> String {
+    state.text_filter.censor(&str)
 }
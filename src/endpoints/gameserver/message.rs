@@ -1,17 +1,108 @@
-use axum::{Router, extract::State, routing::get};
+use axum::{Router, extract::State, routing::get, response::{IntoResponse, Response}, Extension};
+use maud::html as xml;
+use sqlx::types::chrono::NaiveDateTime;
 
-use crate::AppState;
+use crate::{extractors::Xml, types::SessionData, utils::db::db_error, AppState};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/eula", get(eula))
         .route("/announce", get(announce))
+        .route("/news", get(news))
+        .route("/notification", get(notification))
+}
+
+struct Announcement {
+    id: i64,
+    title: String,
+    content: String,
+    starts_at: NaiveDateTime,
 }
 
 async fn eula(State(state): State<AppState>) -> String {
     state.config.eula.clone()
 }
 
-async fn announce(State(state): State<AppState>) -> String {
-    state.config.announcement.clone()
+// announcements that are currently running and meant for the user's game
+async fn active_announcements(
+    session: &SessionData,
+    state: &AppState,
+) -> Result<Vec<Announcement>, Response> {
+    sqlx::query_as!(
+        Announcement,
+        "SELECT id, title, content, starts_at FROM announcements
+        WHERE starts_at <= CURRENT_TIMESTAMP AND (ends_at IS NULL OR ends_at > CURRENT_TIMESTAMP)
+        AND (cardinality(game_versions) = 0 OR $1 = ANY(game_versions))
+        ORDER BY starts_at DESC",
+        session.game_version as i16,
+    )
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)
 }
+
+async fn announce(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let mut announce = state.config.announcement.replace("%username", &session.online_id);
+    for announcement in active_announcements(&session, &state).await? {
+        announce.push_str(&format!("\n{}\n{}\n", announcement.title, announcement.content));
+    }
+    Ok(announce)
+}
+
+async fn news(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let announcements = active_announcements(&session, &state).await?;
+
+    Ok(Xml(xml!(
+        news {
+            subcategory {
+                id { "1" }
+                @for announcement in &announcements {
+                    item {
+                        id { (announcement.id) }
+                        subject { (announcement.title) }
+                        content {
+                            frame width="512" {
+                                title { (announcement.title) }
+                                item width="512" {
+                                    content { (announcement.content) }
+                                }
+                            }
+                        }
+                        date { (announcement.starts_at.timestamp_millis()) }
+                    }
+                }
+            }
+        }
+    )))
+}
+
+// notifications are only shown once
+async fn notification(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let mut notifications = sqlx::query!(
+        "UPDATE notifications SET delivered = TRUE
+        WHERE user_id = $1 AND NOT delivered
+        RETURNING content, created_at",
+        session.user_id,
+    )
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+    notifications.sort_by_key(|n| n.created_at);
+
+    Ok(Xml(xml!(
+        @for notification in &notifications {
+            notification type="moderationNotification" {
+                text { (notification.content) }
+            }
+        }
+    )))
+}
\ No newline at end of file
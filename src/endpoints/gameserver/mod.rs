@@ -1,8 +1,13 @@
-use axum::{Router, http::StatusCode, routing::{get, post}, middleware::{from_fn_with_state, from_fn}};
+use axum::{Router, http::StatusCode, response::Response, routing::{get, post}, middleware::{from_fn_with_state, from_fn}};
+use maud::{html as xml, Markup};
 use serde::{Deserialize, Serialize};
 use tower_http::{services::ServeFile, compression::{CompressionLayer, predicate::SizeAbove, Predicate}};
 
-use crate::{AppState, middleware, types::Config, utils::predicate::ContentType};
+use crate::{
+    AppState, middleware,
+    types::{Config, SessionData},
+    utils::{predicate::ContentType, db::{check_slot, find_developer_slot_id, get_developer_slot_id}},
+};
 
 mod auth;
 mod comment;
@@ -15,6 +20,13 @@ mod search;
 mod slot;
 mod relation;
 mod filter;
+mod rating;
+mod score;
+mod photo;
+mod moderation;
+mod playlist;
+mod review;
+mod stream;
 
 async fn with_auth_and_digest(config: &Config) -> Router<AppState> {
     let mut router = Router::new()
@@ -27,6 +39,14 @@ async fn with_auth_and_digest(config: &Config) -> Router<AppState> {
         .merge(publish::routes())
         .merge(relation::routes())
         .merge(filter::routes())
+        .merge(rating::routes())
+        .merge(score::routes())
+        .merge(photo::routes())
+        .merge(moderation::routes())
+        .merge(playlist::routes())
+        .merge(review::routes())
+        .merge(stream::routes())
+        .route("/npdata", post(auth::npdata))
         .layer(from_fn(middleware::parse_session));
 
     if !config.digest_key.is_empty() && config.verify_client_digest {
@@ -80,9 +100,93 @@ struct Location {
     y: u16,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 enum SlotType {
     User,
     Developer,
 }
+
+// maps the id the game uses for a slot to its row id
+async fn get_slot_id(
+    slot_type: SlotType,
+    id: i64,
+    session: &SessionData,
+    state: &AppState,
+) -> Result<i64, Response> {
+    match slot_type {
+        SlotType::User => {
+            check_slot(id, state).await?;
+            Ok(id)
+        },
+        SlotType::Developer => get_developer_slot_id(id, session.game_version, state).await,
+    }
+}
+
+// same as get_slot_id but for reads, None is a developer slot nobody has interacted with yet
+async fn find_slot_id(
+    slot_type: SlotType,
+    id: i64,
+    state: &AppState,
+) -> Result<Option<i64>, Response> {
+    match slot_type {
+        SlotType::User => {
+            check_slot(id, state).await?;
+            Ok(Some(id))
+        },
+        SlotType::Developer => find_developer_slot_id(id, state).await,
+    }
+}
+
+// developer slots look the same everywhere, only the counts differ
+#[derive(Default)]
+struct DeveloperSlotCounts {
+    heart_count: i64,
+    thumbsup: i64,
+    thumbsdown: i64,
+    average_rating: f64,
+    your_lbp1_rating: i16,
+    your_rating: i16,
+    your_lbp1_play_count: i64,
+    your_lbp2_play_count: i64,
+    comment_count: i64,
+    photo_count: i64,
+    lbp1_play_count: i64,
+    lbp1_completion_count: i64,
+    lbp1_unique_play_count: i64,
+    lbp2_play_count: i64,
+    lbp2_completion_count: i64,
+    lbp2_unique_play_count: i64,
+    lbp3_play_count: i64,
+    lbp3_completion_count: i64,
+    lbp3_unique_play_count: i64,
+}
+
+fn developer_slot(story_id: i64, slot: DeveloperSlotCounts) -> Markup {
+    xml!(
+        slot type="developer" {
+            id { (story_id) }
+            heartCount { (slot.heart_count) }
+            thumbsup { (slot.thumbsup) }
+            thumbsdown { (slot.thumbsdown) }
+            averageRating { (slot.average_rating) } // lbp1
+            yourRating { (slot.your_lbp1_rating) } // lbp1
+            yourDPadRating { (slot.your_rating) } // lbp2+
+            yourlbp1PlayCount { (slot.your_lbp1_play_count) }
+            yourlbp2PlayCount { (slot.your_lbp2_play_count) }
+            commentCount { (slot.comment_count) }
+            photoCount { (slot.photo_count) }
+            playCount { (slot.lbp1_play_count + slot.lbp2_play_count + slot.lbp3_play_count) } // all games
+            completionCount { (slot.lbp1_completion_count + slot.lbp2_completion_count + slot.lbp3_completion_count) } // all games
+            lbp1PlayCount { (slot.lbp1_play_count) }
+            lbp1CompletionCount { (slot.lbp1_completion_count) }
+            lbp1UniquePlayCount { (slot.lbp1_unique_play_count) }
+            lbp2PlayCount { (slot.lbp2_play_count) }
+            lbp2CompletionCount { (slot.lbp2_completion_count) }
+            uniquePlayCount { (slot.lbp2_unique_play_count) }
+            lbp3PlayCount { (slot.lbp3_play_count) }
+            lbp3CompletionCount { (slot.lbp3_completion_count) }
+            lbp3UniquePlayCount { (slot.lbp3_unique_play_count) }
+        }
+    )
+}
\ No newline at end of file
@@ -0,0 +1,143 @@
+use axum::{Router, routing::post, extract::{State, Path}, Extension, response::{IntoResponse, Response}};
+use http::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    extractors::Xml,
+    types::SessionData,
+    utils::{db::{auto_hide_slot, check_moderator, check_slot, db_error}, resource::{get_hash_path, str_to_hash}},
+    AppState,
+};
+
+use super::{get_slot_id, SlotType};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/mmpick/user/:id", post(mmpick))
+        .route("/unmmpick/user/:id", post(unmmpick))
+        .route("/grief", post(grief_report))
+}
+
+async fn mmpick(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(slot_id): Path<i64>,
+) -> Result<impl IntoResponse, Response> {
+    check_moderator(&session, &state).await?;
+    check_slot(slot_id, &state).await?;
+
+    // picking an already picked slot keeps its original date
+    sqlx::query!(
+        "UPDATE slots SET mmpicked_at = CURRENT_TIMESTAMP WHERE id = $1 AND mmpicked_at IS NULL",
+        slot_id,
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn unmmpick(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(slot_id): Path<i64>,
+) -> Result<impl IntoResponse, Response> {
+    check_moderator(&session, &state).await?;
+    check_slot(slot_id, &state).await?;
+
+    sqlx::query!("UPDATE slots SET mmpicked_at = NULL WHERE id = $1", slot_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+struct GriefPlayer {
+    #[serde(rename = "screenName")]
+    screen_name: String,
+}
+
+#[derive(Deserialize)]
+struct GriefPlayerList {
+    #[serde(default)]
+    player: Vec<GriefPlayer>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GriefReportPayload {
+    grief_type_id: i32,
+    level_type: String,
+    #[serde(default)]
+    level_id: i64,
+    level_owner: Option<String>,
+    #[serde(default)]
+    description: String,
+    jpeg_hash: Option<String>,
+    initial_state_hash: Option<String>,
+    grief_state_hash: Option<String>,
+    players: Option<GriefPlayerList>,
+}
+
+async fn grief_report(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Xml<GriefReportPayload>,
+) -> Result<impl IntoResponse, Response> {
+    // the game uploads the screenshot and level states before sending the report
+    let mut hashes = Vec::new();
+    for hash in [&payload.jpeg_hash, &payload.initial_state_hash, &payload.grief_state_hash] {
+        let hash = match hash.as_deref().filter(|h| !h.is_empty()) {
+            Some(hash) => Some(
+                str_to_hash(hash).map_err(|_| (StatusCode::BAD_REQUEST, "Invalid hash").into_response())?
+            ),
+            None => None,
+        };
+        if hash.is_some_and(|h| !get_hash_path(&state.config.resource_dir, h).exists()) {
+            return Err((StatusCode::BAD_REQUEST, "One or more resources don't exist").into_response());
+        }
+        hashes.push(hash.map(hex::encode));
+    }
+
+    // reports from pods, local levels etc. aren't linked to a slot
+    let slot_id = match payload.level_type.as_str() {
+        "user" => Some(get_slot_id(SlotType::User, payload.level_id, &session, &state).await?),
+        "developer" => Some(get_slot_id(SlotType::Developer, payload.level_id, &session, &state).await?),
+        _ => None,
+    };
+
+    let players: Vec<String> = payload.players.iter()
+        .flat_map(|p| &p.player)
+        .map(|p| p.screen_name.clone())
+        .collect();
+
+    sqlx::query!(
+        "INSERT INTO grief_reports (
+            reporter, grief_type, slot_id, level_type, level_owner, description,
+            jpeg_hash, initial_state_hash, grief_state_hash, players
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        session.user_id,
+        payload.grief_type_id,
+        slot_id,
+        payload.level_type,
+        payload.level_owner,
+        payload.description,
+        hashes[0],
+        hashes[1],
+        hashes[2],
+        &players,
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    // user slots get hidden until a moderator looks at them once enough people report them
+    if let (Some(slot_id), threshold @ 1..) = (slot_id, state.config.grief_report_threshold) {
+        auto_hide_slot(&state.pool, slot_id, threshold).await.map_err(db_error)?;
+    }
+
+    Ok(StatusCode::OK)
+}
\ No newline at end of file
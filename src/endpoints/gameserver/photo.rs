@@ -0,0 +1,340 @@
+use axum::{Router, routing::{get, post}, extract::{Path, State}, http::StatusCode, response::{IntoResponse, Response}, Extension};
+use axum_extra::extract::Query;
+use chrono::{DateTime, NaiveDateTime};
+use futures::TryStreamExt;
+use maud::{html as xml, Markup};
+use serde::Deserialize;
+use serde_with::serde_as;
+use sqlx::QueryBuilder;
+use uuid::Uuid;
+
+use crate::{
+    extractors::Xml,
+    types::{Activity, SessionData},
+    utils::{db::{check_slot_visibility, check_user_visibility, db_error, get_id_from_username, record_activity}, resource::get_hash_path},
+    AppState,
+};
+
+use super::{find_slot_id, get_slot_id, SlotType};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/uploadPhoto", post(upload_photo))
+        .route("/deletePhoto/:id", post(delete_photo))
+        .route("/photos/by", get(photos_by))
+        .route("/photos/with", get(photos_with))
+        .route("/photos/:slot_type/:id", get(slot_photos))
+}
+
+#[derive(Deserialize)]
+struct PhotoSlot {
+    #[serde(rename = "@type")]
+    slot_type: String,
+    id: i64,
+}
+
+#[derive(Deserialize)]
+struct PhotoSubject {
+    #[serde(rename = "npHandle")]
+    np_handle: String,
+    bounds: String,
+}
+
+#[derive(Deserialize)]
+struct PhotoSubjectList {
+    #[serde(default)]
+    subject: Vec<PhotoSubject>,
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+struct UploadPhotoPayload {
+    #[serde(rename = "@timestamp")]
+    timestamp: i64,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    small: [u8; 20],
+    #[serde_as(as = "serde_with::hex::Hex")]
+    medium: [u8; 20],
+    #[serde_as(as = "serde_with::hex::Hex")]
+    large: [u8; 20],
+    #[serde_as(as = "serde_with::hex::Hex")]
+    plan: [u8; 20],
+    slot: Option<PhotoSlot>,
+    subjects: Option<PhotoSubjectList>,
+}
+
+async fn upload_photo(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Xml<UploadPhotoPayload>,
+) -> Result<impl IntoResponse, Response> {
+    for hash in [payload.small, payload.medium, payload.large, payload.plan] {
+        if !get_hash_path(&state.config.resource_dir, hash).exists() {
+            return Err((StatusCode::BAD_REQUEST, "One or more resources don't exist").into_response());
+        }
+    }
+
+    let taken_at = parse_timestamp(payload.timestamp)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid timestamp").into_response())?;
+
+    // photos taken in pods, local levels etc. aren't linked to a slot
+    let slot_id = match &payload.slot {
+        Some(slot) => match slot.slot_type.as_str() {
+            "user" => Some(get_slot_id(SlotType::User, slot.id, &session, &state).await?),
+            "developer" => Some(get_slot_id(SlotType::Developer, slot.id, &session, &state).await?),
+            _ => None,
+        },
+        None => None,
+    };
+
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+
+    let photo_id = sqlx::query!(
+        "INSERT INTO photos (author, slot_id, small, medium, large, plan, taken_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id",
+        session.user_id,
+        slot_id,
+        hex::encode(payload.small),
+        hex::encode(payload.medium),
+        hex::encode(payload.large),
+        hex::encode(payload.plan),
+        taken_at,
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .id;
+
+    // subjects without an account on the server are skipped
+    for subject in payload.subjects.iter().flat_map(|s| &s.subject) {
+        sqlx::query!(
+            "INSERT INTO photo_subjects (photo_id, user_id, bounds)
+            SELECT $1, id, $3 FROM users WHERE online_id = $2
+            ON CONFLICT DO NOTHING",
+            photo_id,
+            subject.np_handle,
+            subject.bounds,
+        )
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    }
+
+    tx.commit().await.map_err(db_error)?;
+
+    record_activity(session.user_id, Activity::UploadPhoto { photo_id, slot_id }, &state).await?;
+
+    Ok(StatusCode::OK)
+}
+
+// the game sends photo timestamps in seconds, and expects them back that way
+fn parse_timestamp(timestamp: i64) -> Option<NaiveDateTime> {
+    DateTime::from_timestamp(timestamp, 0).map(|t| t.naive_utc())
+}
+
+fn to_timestamp(taken_at: &NaiveDateTime) -> i64 {
+    taken_at.and_utc().timestamp()
+}
+
+async fn delete_photo(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let is_author = sqlx::query!(
+        "SELECT author = $2 AS is_author FROM photos WHERE id = $1",
+        id,
+        session.user_id,
+    )
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Photo not found").into_response())?
+        .is_author
+        .unwrap();
+
+    if !is_author {
+        return Err((StatusCode::UNAUTHORIZED, "Cannot delete another user's photo").into_response());
+    }
+
+    sqlx::query!("DELETE FROM photos WHERE id = $1", id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PhotoListQuery {
+    page_start: i64,
+    page_size: i64,
+}
+
+#[derive(Deserialize)]
+struct PhotoUserQuery {
+    user: String,
+}
+
+enum PhotoFilter {
+    By(Uuid),
+    With(Uuid),
+    Slot(i64),
+}
+
+#[derive(sqlx::FromRow)]
+struct Photo {
+    id: i64,
+    author_oid: String,
+    slot_id: Option<i64>,
+    story_id: Option<i64>,
+    small: String,
+    medium: String,
+    large: String,
+    plan: String,
+    taken_at: NaiveDateTime,
+    subject_oids: Vec<String>,
+    subject_bounds: Vec<String>,
+}
+
+async fn photos_by(
+    query: Query<PhotoListQuery>,
+    query2: Query<PhotoUserQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let user_id = get_id_from_username(&query2.user, &state).await?;
+    check_user_visibility(user_id, &session, &state).await?;
+    photos(PhotoFilter::By(user_id), query, session, state).await
+}
+
+async fn photos_with(
+    query: Query<PhotoListQuery>,
+    query2: Query<PhotoUserQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let user_id = get_id_from_username(&query2.user, &state).await?;
+    check_user_visibility(user_id, &session, &state).await?;
+    photos(PhotoFilter::With(user_id), query, session, state).await
+}
+
+async fn slot_photos(
+    Path((slot_type, id)): Path<(SlotType, i64)>,
+    query: Query<PhotoListQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let Some(slot_id) = find_slot_id(slot_type, id, &state).await? else {
+        return Ok(Xml(xml!(photos {})));
+    };
+    check_slot_visibility(slot_id, &session, &state).await?;
+    photos(PhotoFilter::Slot(slot_id), query, session, state).await
+}
+
+async fn photos(
+    filter: PhotoFilter,
+    params: Query<PhotoListQuery>,
+    session: Extension<SessionData>,
+    state: AppState,
+) -> Result<Xml<Markup>, Response> {
+    let mut query = QueryBuilder::new(
+        "SELECT photos.id, photos.slot_id, photos.small, photos.medium, photos.large, photos.plan, photos.taken_at,
+        author.online_id AS author_oid,
+        slots.story_id,
+        ARRAY(
+            SELECT users.online_id FROM photo_subjects JOIN users ON photo_subjects.user_id = users.id
+            WHERE photo_id = photos.id ORDER BY users.id
+        ) AS subject_oids,
+        ARRAY(
+            SELECT bounds FROM photo_subjects
+            WHERE photo_id = photos.id ORDER BY user_id
+        ) AS subject_bounds
+        FROM photos
+        JOIN users author ON photos.author = author.id
+        LEFT JOIN slots ON photos.slot_id = slots.id
+        LEFT JOIN users slot_author ON slots.author = slot_author.id"
+    );
+
+    // photos of hidden profiles and levels stay hidden too
+    query.push(" WHERE (author.profile_visibility != 'myself' OR author.id = ");
+    query.push_bind(session.user_id);
+    query.push(") AND (slot_author.id IS NULL OR slot_author.level_visibility != 'myself' OR slot_author.id = ");
+    query.push_bind(session.user_id);
+    query.push(") AND (slots.id IS NULL OR slots.hidden = FALSE OR slots.author = ");
+    query.push_bind(session.user_id);
+    query.push(')');
+
+    match filter {
+        PhotoFilter::By(user_id) => {
+            query.push(" AND photos.author = ");
+            query.push_bind(user_id);
+        }
+        PhotoFilter::With(user_id) => {
+            query.push(" AND EXISTS(SELECT photo_id FROM photo_subjects WHERE photo_id = photos.id AND user_id = ");
+            query.push_bind(user_id);
+            query.push(')');
+        }
+        PhotoFilter::Slot(slot_id) => {
+            query.push(" AND photos.slot_id = ");
+            query.push_bind(slot_id);
+        }
+    }
+
+    query.push(" ORDER BY photos.taken_at DESC");
+    query.push(" LIMIT ");
+    query.push_bind(params.page_size);
+    query.push(" OFFSET ");
+    query.push_bind(params.page_start - 1);
+
+    let mut photos = query.build_query_as::<Photo>()
+        .fetch(&state.pool);
+
+    Ok(Xml(xml!(
+        photos {
+            @while let Some(photo) = photos.try_next().await.map_err(db_error)? {
+                photo timestamp=(to_timestamp(&photo.taken_at)) {
+                    id { (photo.id) }
+                    small { (photo.small) }
+                    medium { (photo.medium) }
+                    large { (photo.large) }
+                    plan { (photo.plan) }
+                    author { (photo.author_oid) }
+                    @match (photo.slot_id, photo.story_id) {
+                        (_, Some(story_id)) => slot type="developer" { id { (story_id) } },
+                        (Some(slot_id), None) => slot type="user" { id { (slot_id) } },
+                        (None, None) => {},
+                    }
+                    subjects {
+                        @for (np_handle, bounds) in photo.subject_oids.iter().zip(&photo.subject_bounds) {
+                            subject {
+                                npHandle { (np_handle) }
+                                displayName { (np_handle) }
+                                bounds { (bounds) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamp_round_trip() {
+        for timestamp in [0, 1_300_000_000, 1_710_000_000] {
+            assert_eq!(to_timestamp(&parse_timestamp(timestamp).unwrap()), timestamp);
+        }
+    }
+
+    #[test]
+    fn invalid_timestamp() {
+        assert!(parse_timestamp(i64::MAX).is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,450 @@
+use axum::{Router, routing::{get, post}, extract::{Path, State}, http::StatusCode, response::{IntoResponse, Response}, Extension};
+use axum_extra::extract::Query;
+use maud::{html as xml, Markup};
+use serde::Deserialize;
+use sqlx::{PgConnection, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    extractors::Xml,
+    types::{ResourceRef, SessionData},
+    utils::db::{check_playlist_author, check_user_visibility, db_error, get_id_from_username},
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/playlists", post(create_playlist))
+        .route("/playlists/:id", post(update_playlist))
+        .route("/playlists/:id/delete", post(delete_playlist))
+        .route("/playlists/:id/slots", post(add_playlist_slots))
+        .route("/playlists/:id/slots/:slot_id/delete", post(remove_playlist_slot))
+        .route("/playlists/:id/order_slots", post(order_playlist_slots))
+        .route("/user/:online_id/playlists", get(user_playlists))
+        .route("/favouritePlaylists/:online_id", get(favourite_playlists))
+}
+
+#[derive(Deserialize)]
+struct PlaylistPayload {
+    name: Option<String>,
+    description: Option<String>,
+    icon: Option<ResourceRef>,
+    #[serde(default)]
+    level_id: Vec<i64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct Playlist {
+    id: i64,
+    name: String,
+    description: String,
+    icon: String,
+    author_oid: String,
+    slot_icons: Vec<String>,
+    slot_count: i64,
+    heart_count: i64,
+    total: i64,
+}
+
+enum PlaylistFilter {
+    Id(i64),
+    By(Uuid),
+    HeartedBy(Uuid),
+}
+
+async fn create_playlist(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Xml<PlaylistPayload>,
+) -> Result<impl IntoResponse, Response> {
+    let name = payload.name.as_deref()
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "Playlist needs a name").into_response())?;
+    check_text(&payload).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+    if let Some(icon) = &payload.icon {
+        if !icon.exists(&state.config.resource_dir) {
+            return Err((StatusCode::BAD_REQUEST, "Icon resource invalid").into_response());
+        }
+    }
+
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+
+    // locking the author's row makes concurrent creates wait here, so they can't all get past the limit
+    sqlx::query!("SELECT id FROM users WHERE id = $1 FOR UPDATE", session.user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    let num_playlists = sqlx::query!("SELECT COUNT(*) FROM playlists WHERE author = $1", session.user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .count
+        .unwrap_or_default();
+
+    if num_playlists >= state.config.list_limit.into() {
+        return Err((StatusCode::UNAUTHORIZED, "User has reached playlist limit").into_response());
+    }
+
+    let playlist_id = sqlx::query!(
+        "INSERT INTO playlists (author, name, description, icon) VALUES ($1, $2, $3, $4) RETURNING id",
+        session.user_id,
+        state.text_filter.censor(name),
+        state.text_filter.censor(payload.description.as_deref().unwrap_or_default()),
+        payload.icon.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+    )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .id;
+
+    add_slots(playlist_id, &payload.level_id, &mut tx).await?;
+    tx.commit().await.map_err(db_error)?;
+
+    single_playlist(playlist_id, &session, &state).await
+}
+
+async fn update_playlist(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Xml<PlaylistPayload>,
+) -> Result<impl IntoResponse, Response> {
+    check_playlist_author(id, session.user_id, &state).await?;
+    check_text(&payload).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+    if let Some(icon) = &payload.icon {
+        if !icon.exists(&state.config.resource_dir) {
+            return Err((StatusCode::BAD_REQUEST, "Icon resource invalid").into_response());
+        }
+    }
+
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    sqlx::query!(
+        "UPDATE playlists SET
+        name = COALESCE($2, name),
+        description = COALESCE($3, description),
+        icon = COALESCE($4, icon)
+        WHERE id = $1",
+        id,
+        payload.name.as_deref().map(|n| state.text_filter.censor(n)),
+        payload.description.as_deref().map(|d| state.text_filter.censor(d)),
+        payload.icon.as_ref().map(|r| r.to_string()),
+    )
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+    add_slots(id, &payload.level_id, &mut tx).await?;
+    tx.commit().await.map_err(db_error)?;
+
+    single_playlist(id, &session, &state).await
+}
+
+async fn delete_playlist(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    check_playlist_author(id, session.user_id, &state).await?;
+
+    sqlx::query!("DELETE FROM playlists WHERE id = $1", id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn add_playlist_slots(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Xml<PlaylistPayload>,
+) -> Result<impl IntoResponse, Response> {
+    check_playlist_author(id, session.user_id, &state).await?;
+
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    add_slots(id, &payload.level_id, &mut tx).await?;
+    tx.commit().await.map_err(db_error)?;
+
+    single_playlist(id, &session, &state).await
+}
+
+async fn remove_playlist_slot(
+    Path((id, slot_id)): Path<(i64, i64)>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    check_playlist_author(id, session.user_id, &state).await?;
+
+    sqlx::query!("DELETE FROM playlist_slots WHERE playlist_id = $1 AND slot_id = $2", id, slot_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+// the game sends every slot of the playlist in its new order
+async fn order_playlist_slots(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Xml<PlaylistPayload>,
+) -> Result<impl IntoResponse, Response> {
+    check_playlist_author(id, session.user_id, &state).await?;
+
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    for (position, slot_id) in payload.level_id.iter().enumerate() {
+        sqlx::query!(
+            "UPDATE playlist_slots SET position = $3 WHERE playlist_id = $1 AND slot_id = $2",
+            id,
+            slot_id,
+            position as i32,
+        )
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    }
+    tx.commit().await.map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+// the columns are varchar(64) and varchar(512), longer text would only fail in the database
+fn check_text(payload: &PlaylistPayload) -> Result<(), &'static str> {
+    if payload.name.as_ref().is_some_and(|n| n.chars().count() > 64) {
+        return Err("Playlist name is too long");
+    }
+    if payload.description.as_ref().is_some_and(|d| d.chars().count() > 512) {
+        return Err("Playlist description is too long");
+    }
+
+    Ok(())
+}
+
+// new slots go to the end of the playlist, slots that are already in it are skipped.
+// every id is checked before anything gets added, so a bad one doesn't leave the playlist half updated
+async fn add_slots(
+    playlist_id: i64,
+    slot_ids: &[i64],
+    conn: &mut PgConnection,
+) -> Result<(), Response> {
+    if slot_ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut unique_ids = slot_ids.to_vec();
+    unique_ids.sort_unstable();
+    unique_ids.dedup();
+
+    let num_found = sqlx::query!(
+        "SELECT COUNT(*) FROM slots WHERE id = ANY($1) AND story_id IS NULL",
+        &unique_ids,
+    )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(db_error)?
+        .count
+        .unwrap_or_default();
+
+    if num_found != unique_ids.len() as i64 {
+        return Err((StatusCode::NOT_FOUND, "Slot not found").into_response());
+    }
+
+    sqlx::query!(
+        "INSERT INTO playlist_slots (playlist_id, slot_id, position)
+        SELECT $1, new.slot_id,
+        ((SELECT COALESCE(MAX(position) + 1, 0) FROM playlist_slots WHERE playlist_id = $1) + new.n - 1)::int
+        FROM unnest($2::bigint[]) WITH ORDINALITY AS new(slot_id, n)
+        ON CONFLICT DO NOTHING",
+        playlist_id,
+        slot_ids,
+    )
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error)?;
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaylistListQuery {
+    page_start: i64,
+    page_size: i64,
+}
+
+async fn user_playlists(
+    Path(online_id): Path<String>,
+    query: Query<PlaylistListQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let user_id = get_id_from_username(&online_id, &state).await?;
+    check_user_visibility(user_id, &session, &state).await?;
+
+    let (playlists, total, hint_start) = playlists(PlaylistFilter::By(user_id), query.page_start, query.page_size, &session, &state).await?;
+    Ok(Xml(xml!(
+        playlists total=(total) hint_start=(hint_start) { (playlists) }
+    )))
+}
+
+async fn favourite_playlists(
+    Path(online_id): Path<String>,
+    query: Query<PlaylistListQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let user_id = get_id_from_username(&online_id, &state).await?;
+    check_user_visibility(user_id, &session, &state).await?;
+
+    let (playlists, total, hint_start) = playlists(PlaylistFilter::HeartedBy(user_id), query.page_start, query.page_size, &session, &state).await?;
+    Ok(Xml(xml!(
+        favouritePlaylists total=(total) hint_start=(hint_start) { (playlists) }
+    )))
+}
+
+async fn single_playlist(
+    playlist_id: i64,
+    session: &SessionData,
+    state: &AppState,
+) -> Result<Xml<Markup>, Response> {
+    let (playlist, total, _) = playlists(PlaylistFilter::Id(playlist_id), 1, 1, session, state).await?;
+    if total == 0 {
+        return Err((StatusCode::NOT_FOUND, "Playlist not found").into_response());
+    }
+    Ok(Xml(playlist))
+}
+
+async fn playlists(
+    filter: PlaylistFilter,
+    page_start: i64,
+    page_size: i64,
+    session: &SessionData,
+    state: &AppState,
+) -> Result<(Markup, i64, usize), Response> {
+    let mut query = QueryBuilder::new(
+        "SELECT playlists.id, playlists.name, playlists.description, playlists.icon,
+        author.online_id AS author_oid,
+        visible_slots.icons AS slot_icons,
+        visible_slots.count AS slot_count,
+        (SELECT COUNT(*) FROM favourite_playlists WHERE playlist_id = playlists.id) AS heart_count,
+        COUNT(*) OVER() AS total
+        FROM playlists
+        JOIN users author ON playlists.author = author.id"
+    );
+    // the icons and count only cover slots the viewer would also see in the playlist's slot list
+    query.push(
+        " CROSS JOIN LATERAL (
+            SELECT COALESCE(array_agg(slots.icon ORDER BY playlist_slots.position), '{}') AS icons, COUNT(*) AS count
+            FROM playlist_slots
+            JOIN slots ON playlist_slots.slot_id = slots.id
+            JOIN users slot_author ON slots.author = slot_author.id
+            WHERE playlist_slots.playlist_id = playlists.id AND (slots.hidden = FALSE OR slots.author = "
+    );
+    query.push_bind(session.user_id);
+    query.push(") AND (slot_author.level_visibility != 'myself' OR slots.author = ");
+    query.push_bind(session.user_id);
+    query.push(")) AS visible_slots");
+    match filter {
+        PlaylistFilter::Id(playlist_id) => {
+            query.push(" WHERE playlists.id = ");
+            query.push_bind(playlist_id);
+        }
+        PlaylistFilter::By(user_id) => {
+            query.push(" WHERE playlists.author = ");
+            query.push_bind(user_id);
+            query.push(" ORDER BY playlists.created_at DESC");
+        }
+        PlaylistFilter::HeartedBy(user_id) => {
+            query.push(" JOIN favourite_playlists hearts ON playlists.id = hearts.playlist_id AND hearts.user_id = ");
+            query.push_bind(user_id);
+            query.push(" ORDER BY hearts.timestamp DESC");
+        }
+    }
+    query.push(" LIMIT ");
+    query.push_bind(page_size);
+    query.push(" OFFSET ");
+    query.push_bind(page_start - 1);
+
+    let playlists = query.build_query_as::<Playlist>()
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    let total = playlists.first().map_or(0, |p| p.total);
+    let hint_start = playlists.len() + 1;
+
+    Ok((xml!(
+        @for playlist in &playlists {
+            playlist {
+                id { (playlist.id) }
+                name { (playlist.name) }
+                description { (playlist.description) }
+                author {
+                    npHandle { (playlist.author_oid) }
+                }
+                icon { (playlist.icon) }
+                icons {
+                    @for icon in &playlist.slot_icons {
+                        icon { (icon) }
+                    }
+                }
+                levels { (playlist.slot_count) }
+                hearts { (playlist.heart_count) }
+            }
+        }
+    ), total, hint_start))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use crate::utils::db::testing::{create_slot, create_user};
+
+    use super::*;
+
+    async fn create_playlist(pool: &PgPool, author: Uuid) -> i64 {
+        sqlx::query!("INSERT INTO playlists (author, name) VALUES ($1, 'test') RETURNING id", author)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .id
+    }
+
+    async fn playlist_slots(pool: &PgPool, playlist_id: i64) -> Vec<i64> {
+        sqlx::query!("SELECT slot_id FROM playlist_slots WHERE playlist_id = $1 ORDER BY position", playlist_id)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.slot_id)
+            .collect()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn slots_are_added_in_order_after_existing_ones(pool: PgPool) {
+        let user = create_user(&pool).await;
+        let playlist_id = create_playlist(&pool, user).await;
+        let first = create_slot(&pool, user).await;
+        let second = create_slot(&pool, user).await;
+        let third = create_slot(&pool, user).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        add_slots(playlist_id, &[second], &mut conn).await.unwrap();
+        add_slots(playlist_id, &[third, second, first, third], &mut conn).await.unwrap();
+        assert_eq!(playlist_slots(&pool, playlist_id).await, vec![second, third, first]);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn unknown_slot_adds_nothing(pool: PgPool) {
+        let user = create_user(&pool).await;
+        let playlist_id = create_playlist(&pool, user).await;
+        let slot_id = create_slot(&pool, user).await;
+
+        let mut conn = pool.acquire().await.unwrap();
+        assert!(add_slots(playlist_id, &[slot_id, -1], &mut conn).await.is_err());
+        assert!(playlist_slots(&pool, playlist_id).await.is_empty());
+    }
+}
\ No newline at end of file
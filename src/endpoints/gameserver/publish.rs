@@ -1,11 +1,13 @@
 use axum::{Router, routing::post, extract::{State, Path}, response::{IntoResponse, Response}, Extension, http::StatusCode};
 use maud::html as xml;
 use serde::Deserialize;
-use serde_with::{serde_as, BoolFromInt, DisplayFromStr};
+use serde_with::{serde_as, formats::CommaSeparator, BoolFromInt, DisplayFromStr, StringWithSeparator};
+use tracing::warn;
 
 use crate::{
     extractors::Xml,
-    types::{SessionData, ResourceRef}, AppState, utils::db::{db_error, check_slot_author},
+    types::{Activity, Label, SessionData, ResourceRef}, AppState,
+    utils::{db::{db_error, check_slot_author, record_activity}, resource::{walk_dependencies, DependencyWalk}},
 };
 
 use super::Location;
@@ -43,8 +45,9 @@ struct SlotPublishPayload {
     leveltype: String,
     min_players: u8,
     max_players: u8,
-    //#[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
-    //TODO: labels: Option<Vec<String>>,
+    #[serde(default)]
+    #[serde_as(as = "Option<StringWithSeparator::<CommaSeparator, String>>")]
+    labels: Option<Vec<String>>,
     #[serde(default)]
     move_required: bool,
     #[serde(default)]
@@ -58,17 +61,42 @@ async fn start_publish(
     let mut resources: Vec<ResourceRef> = payload.resource.iter().map(|r| ResourceRef::Hash(*r)).collect();
     resources.push(payload.icon.clone());
 
+    let mut missing: Vec<String> = resources.iter()
+        .filter(|r| !r.exists(&state.config.resource_dir))
+        .map(ResourceRef::to_string)
+        .collect();
+
+    // the client's list can be incomplete, so anything the root level needs gets asked for too
+    let walk = walk_level(payload.root_level, &state).await?;
+    for hash in walk.missing {
+        let hash = hex::encode(hash);
+        if !missing.contains(&hash) {
+            missing.push(hash);
+        }
+    }
+
     Ok(Xml(xml!(
         slot type="user" {
-            @for resource in resources {
-                @if !resource.exists(&state.config.resource_dir) {
-                    resource { (resource.to_string()) }
-                }
+            @for resource in missing {
+                resource { (resource) }
             }
         }
     )))
 }
 
+// every resource in the level gets opened and parsed, which is too slow to do on the async runtime
+async fn walk_level(root_level: [u8; 20], state: &AppState) -> Result<DependencyWalk, Response> {
+    let resource_dir = state.config.resource_dir.clone();
+    tokio::task::spawn_blocking(move || walk_dependencies(&resource_dir, root_level))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .map_err(|e| {
+            // resources uploaded before they were validated can make otherwise fine levels fail here
+            warn!("Couldn't walk the dependencies of level {}: {e:#}", hex::encode(root_level));
+            (StatusCode::BAD_REQUEST, format!("Invalid level: {e:#}")).into_response()
+        })
+}
+
 async fn publish(
     State(state): State<AppState>,
     session: Extension<SessionData>,
@@ -103,30 +131,37 @@ async fn publish(
         Some(id) => check_slot_author(id, session.user_id, &state).await?,
     }
 
-    let mut resources: Vec<ResourceRef> = pl.resource.iter().map(|r| ResourceRef::Hash(*r)).collect();
-    resources.push(pl.icon.clone());
-    resources.push(ResourceRef::Hash(pl.root_level));
-    for res in resources {
-        if !res.exists(&state.config.resource_dir) {
-            return Err((StatusCode::BAD_REQUEST, "One or more resources don't exist").into_response());
-        }
+    if !pl.icon.exists(&state.config.resource_dir) {
+        return Err((StatusCode::BAD_REQUEST, "One or more resources don't exist").into_response());
+    }
+
+    // the client's resource list isn't trusted, what gets stored is what the root level actually depends on
+    let walk = walk_level(pl.root_level, &state).await?;
+    if !walk.missing.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "One or more resources don't exist").into_response());
     }
 
     // TODO: add checks based on game version
 
-    let res_array: Vec<String> = pl.resource.iter().map(hex::encode).collect();
+    let res_array: Vec<String> = walk.dependencies.iter().map(hex::encode).collect();
+    // unknown labels are dropped rather than failing the whole publish, same as reviews
+    let labels: Vec<String> = pl.labels.iter()
+        .flatten()
+        .filter(|l| Label::parse(l).is_some())
+        .cloned()
+        .collect();
 
     let slot_id = match pl.id {
         None => sqlx::query!(
             "INSERT INTO slots (
                 name, author, description, icon, gamever, root_level, resources, location_x, location_y,
                 initially_locked, is_sub_level, is_lbp1_only, shareable, level_type,
-                min_players, max_players, move_required, vita_cc_required
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+                min_players, max_players, move_required, vita_cc_required, labels
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
             RETURNING id",
-            pl.name,
+            state.text_filter.censor(&pl.name),
             session.user_id,
-            pl.description,
+            state.text_filter.censor(&pl.description),
             pl.icon.to_string(),
             session.game_version as i16,
             hex::encode(pl.root_level),
@@ -141,7 +176,8 @@ async fn publish(
             pl.min_players as i16,
             pl.max_players as i16,
             pl.move_required,
-            pl.vita_cross_control_required
+            pl.vita_cross_control_required,
+            labels.as_slice(),
         )
         .fetch_one(&state.pool)
         .await
@@ -163,11 +199,11 @@ async fn publish(
                 "UPDATE slots
                 SET name=$1, description=$2, icon=$3, gamever=$4, root_level=$5, resources=$6, location_x=$7, location_y=$8,
                     initially_locked=$9, is_sub_level=$10, is_lbp1_only=$11, shareable=$12, level_type=$13,
-                    min_players=$14, max_players=$15, move_required=$16, vita_cc_required=$17,
+                    min_players=$14, max_players=$15, move_required=$16, vita_cc_required=$17, labels=$18,
                     updated_at=CURRENT_TIMESTAMP
-                WHERE id = $18",
-                pl.name,
-                pl.description,
+                WHERE id = $19",
+                state.text_filter.censor(&pl.name),
+                state.text_filter.censor(&pl.description),
                 pl.icon.to_string(),
                 match query.root_lvl_changed.unwrap() {
                     true => session.game_version as i16,
@@ -186,6 +222,7 @@ async fn publish(
                 pl.max_players as i16,
                 pl.move_required,
                 pl.vita_cross_control_required,
+                labels.as_slice(),
                 id,
             )
                 .execute(&state.pool)
@@ -196,6 +233,10 @@ async fn publish(
         },
     };
 
+    if pl.id.is_none() {
+        record_activity(session.user_id, Activity::PublishLevel(slot_id), &state).await?;
+    }
+
     Ok(Xml(xml!(
         slot type="user" {
             id { (slot_id) }
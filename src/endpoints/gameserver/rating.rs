@@ -0,0 +1,71 @@
+use axum::{Router, routing::post, extract::{State, Path}, Extension, response::{IntoResponse, Response}};
+use axum_extra::extract::Query;
+use http::StatusCode;
+use serde::Deserialize;
+
+use crate::{AppState, types::SessionData, utils::db::{check_slot, db_error}};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/rate/user/:id", post(rate))
+        .route("/dpadrate/user/:id", post(dpad_rate))
+}
+
+#[derive(Deserialize)]
+struct RatingQuery {
+    rating: i16,
+}
+
+// lbp1 star rating
+async fn rate(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(slot_id): Path<i64>,
+    query: Query<RatingQuery>,
+) -> Result<impl IntoResponse, Response> {
+    if !(1..=5).contains(&query.rating) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid rating").into_response());
+    }
+    check_slot(slot_id, &state).await?;
+
+    sqlx::query!(
+        "INSERT INTO rated_slots (user_id, slot_id, lbp1_rating) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, slot_id) DO UPDATE
+        SET lbp1_rating = EXCLUDED.lbp1_rating, timestamp = CURRENT_TIMESTAMP",
+        session.user_id,
+        slot_id,
+        query.rating,
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+// lbp2+ thumbs up/down, 0 is neutral
+async fn dpad_rate(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(slot_id): Path<i64>,
+    query: Query<RatingQuery>,
+) -> Result<impl IntoResponse, Response> {
+    if !(-1..=1).contains(&query.rating) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid rating").into_response());
+    }
+    check_slot(slot_id, &state).await?;
+
+    sqlx::query!(
+        "INSERT INTO rated_slots (user_id, slot_id, rating) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, slot_id) DO UPDATE
+        SET rating = EXCLUDED.rating, timestamp = CURRENT_TIMESTAMP",
+        session.user_id,
+        slot_id,
+        query.rating,
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
@@ -1,26 +1,32 @@
 use axum::{Router, routing::post, extract::{State, Path}, Extension, response::{IntoResponse, Response}};
 use http::StatusCode;
+use sqlx::PgExecutor;
+use uuid::Uuid;
 
-use crate::{AppState, types::SessionData, utils::db::{check_slot, db_error, is_slot_hearted, is_slot_queued}};
+use crate::{AppState, types::{Activity, GameVersion, SessionData}, utils::db::{check_playlist, check_slot, db_error, get_id_from_username, is_playlist_hearted, is_slot_hearted, is_slot_queued, is_user_hearted, notify, record_activity}};
 
-use super::SlotType;
+use super::{get_slot_id, SlotType};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/favourite/slot/:type/:id", post(favourite_slot))
         .route("/unfavourite/slot/:type/:id", post(unfavourite_slot))
+        .route("/favourite/user/:online_id", post(favourite_user))
+        .route("/unfavourite/user/:online_id", post(unfavourite_user))
+        .route("/favourite/playlist/:id", post(favourite_playlist))
+        .route("/unfavourite/playlist/:id", post(unfavourite_playlist))
         .route("/lolcatftw/add/user/:id", post(queue_slot))
         .route("/lolcatftw/remove/user/:id", post(unqueue_slot))
         .route("/enterLevel/:type/:id", post(enter_level))
+        .route("/play/user/:id", post(play_level))
 }
 
 async fn favourite_slot(
     State(state): State<AppState>,
     session: Extension<SessionData>,
-    // TODO: handle slot type
-    Path((_, slot_id)): Path<(SlotType, i64)>
+    Path((slot_type, id)): Path<(SlotType, i64)>
 ) -> Result<impl IntoResponse, Response> {
-    check_slot(slot_id, &state).await?;
+    let slot_id = get_slot_id(slot_type, id, &session, &state).await?;
     if is_slot_hearted(session.user_id, slot_id, &state).await? {
         return Err((StatusCode::UNAUTHORIZED, "Slot is already hearted").into_response())
     }
@@ -30,15 +36,27 @@ async fn favourite_slot(
         .await
         .map_err(db_error)?;
 
+    record_activity(session.user_id, Activity::HeartLevel(slot_id), &state).await?;
+
+    // developer slots don't have an author to notify
+    let slot = sqlx::query!("SELECT author, name FROM slots WHERE id = $1", slot_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    if let Some(author) = slot.author.filter(|author| *author != session.user_id) {
+        notify(author, &format!("{} hearted your level {}", session.online_id, slot.name), &state).await?;
+    }
+
     Ok(())
 }
 
 async fn unfavourite_slot(
     State(state): State<AppState>,
     session: Extension<SessionData>,
-    // TODO: handle slot type
-    Path((_, slot_id)): Path<(SlotType, i64)>
+    Path((slot_type, id)): Path<(SlotType, i64)>
 ) -> Result<impl IntoResponse, Response> {
+    let slot_id = get_slot_id(slot_type, id, &session, &state).await?;
     if !is_slot_hearted(session.user_id, slot_id, &state).await? {
         return Err((StatusCode::UNAUTHORIZED, "Slot is not hearted").into_response())
     }
@@ -51,6 +69,82 @@ async fn unfavourite_slot(
     Ok(())
 }
 
+async fn favourite_user(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(online_id): Path<String>,
+) -> Result<impl IntoResponse, Response> {
+    let target_id = get_id_from_username(&online_id, &state).await?;
+    if target_id == session.user_id {
+        return Err((StatusCode::BAD_REQUEST, "Cannot heart yourself").into_response())
+    }
+    if is_user_hearted(session.user_id, target_id, &state).await? {
+        return Err((StatusCode::UNAUTHORIZED, "User is already hearted").into_response())
+    }
+
+    sqlx::query!("INSERT INTO favourite_users (user_id, target_id) VALUES ($1, $2)", session.user_id, target_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    record_activity(session.user_id, Activity::HeartUser(target_id), &state).await?;
+
+    Ok(())
+}
+
+async fn unfavourite_user(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(online_id): Path<String>,
+) -> Result<impl IntoResponse, Response> {
+    let target_id = get_id_from_username(&online_id, &state).await?;
+    if !is_user_hearted(session.user_id, target_id, &state).await? {
+        return Err((StatusCode::UNAUTHORIZED, "User is not hearted").into_response())
+    }
+
+    sqlx::query!("DELETE FROM favourite_users WHERE user_id = $1 AND target_id = $2", session.user_id, target_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(())
+}
+
+async fn favourite_playlist(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(playlist_id): Path<i64>,
+) -> Result<impl IntoResponse, Response> {
+    check_playlist(playlist_id, &state).await?;
+    if is_playlist_hearted(session.user_id, playlist_id, &state).await? {
+        return Err((StatusCode::UNAUTHORIZED, "Playlist is already hearted").into_response())
+    }
+
+    sqlx::query!("INSERT INTO favourite_playlists (user_id, playlist_id) VALUES ($1, $2)", session.user_id, playlist_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(())
+}
+
+async fn unfavourite_playlist(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(playlist_id): Path<i64>,
+) -> Result<impl IntoResponse, Response> {
+    if !is_playlist_hearted(session.user_id, playlist_id, &state).await? {
+        return Err((StatusCode::UNAUTHORIZED, "Playlist is not hearted").into_response())
+    }
+
+    sqlx::query!("DELETE FROM favourite_playlists WHERE user_id = $1 AND playlist_id = $2", session.user_id, playlist_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(())
+}
+
 async fn queue_slot(
     State(state): State<AppState>,
     session: Extension<SessionData>,
@@ -86,16 +180,215 @@ async fn unqueue_slot(
     Ok(())
 }
 
-// TODO: handle the fucking slot type, add play count bs
 async fn enter_level(
     State(state): State<AppState>,
     session: Extension<SessionData>,
-    Path((_, slot_id)): Path<(SlotType, i64)>,
+    Path((slot_type, id)): Path<(SlotType, i64)>,
 ) -> Result<StatusCode, Response> {
+    let slot_id = get_slot_id(slot_type, id, &session, &state).await?;
+
     sqlx::query!("DELETE FROM queued_slots WHERE user_id = $1 AND slot_id = $2", session.user_id, slot_id)
         .execute(&state.pool)
         .await
         .map_err(db_error)?;
 
+    record_play(&session, slot_id, PlaySource::EnterLevel, &state).await?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn play_level(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(slot_id): Path<i64>,
+) -> Result<StatusCode, Response> {
+    check_slot(slot_id, &state).await?;
+    record_play(&session, slot_id, PlaySource::Play, &state).await?;
     Ok(StatusCode::OK)
+}
+
+// the game can report the same attempt through both /enterLevel and /play, so a /play this
+// soon after an /enterLevel that hasn't been paired with one yet doesn't count again
+const PLAY_DEDUPE_SECS: f64 = 30.0;
+
+#[derive(Clone, Copy)]
+enum PlaySource {
+    EnterLevel,
+    Play,
+}
+
+async fn record_play(
+    session: &SessionData,
+    slot_id: i64,
+    source: PlaySource,
+    state: &AppState,
+) -> Result<(), Response> {
+    let inserted = insert_play(&state.pool, session.user_id, slot_id, session.game_version, source)
+        .await
+        .map_err(db_error)?;
+
+    if inserted {
+        record_activity(session.user_id, Activity::PlayLevel(slot_id), state).await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_play(
+    conn: impl PgExecutor<'_>,
+    user_id: Uuid,
+    slot_id: i64,
+    game_version: GameVersion,
+    source: PlaySource,
+) -> Result<bool, sqlx::Error> {
+    Ok(match source {
+        PlaySource::EnterLevel => {
+            sqlx::query!(
+                "INSERT INTO played_slots (user_id, slot_id, gamever, awaiting_play) VALUES ($1, $2, $3, TRUE)",
+                user_id,
+                slot_id,
+                game_version as i16,
+            )
+                .execute(conn)
+                .await?;
+            true
+        },
+        // claiming the row and inserting happen in one statement, and the row lock
+        // keeps two concurrent requests from both claiming the same entry
+        PlaySource::Play => sqlx::query!(
+            "WITH claimed AS (
+                UPDATE played_slots SET awaiting_play = FALSE
+                WHERE id = (
+                    SELECT id FROM played_slots
+                    WHERE user_id = $1 AND slot_id = $2 AND awaiting_play
+                    AND timestamp > CURRENT_TIMESTAMP - make_interval(secs => $4)
+                    ORDER BY timestamp DESC LIMIT 1
+                    FOR UPDATE
+                ) AND awaiting_play
+                RETURNING id
+            )
+            INSERT INTO played_slots (user_id, slot_id, gamever)
+            SELECT $1::uuid, $2::bigint, $3::smallint
+            WHERE NOT EXISTS(SELECT id FROM claimed)
+            RETURNING id",
+            user_id,
+            slot_id,
+            game_version as i16,
+            PLAY_DEDUPE_SECS,
+        )
+            .fetch_optional(conn)
+            .await?
+            .is_some(),
+    })
+}
+
+// the game only tells us a level was completed by submitting a score,
+// which marks the user's latest play of it that isn't completed yet
+pub async fn record_completion(
+    conn: impl PgExecutor<'_>,
+    user_id: Uuid,
+    slot_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE played_slots SET completed = TRUE
+        WHERE id = (
+            SELECT id FROM played_slots
+            WHERE user_id = $1 AND slot_id = $2 AND NOT completed
+            ORDER BY timestamp DESC LIMIT 1
+        )",
+        user_id,
+        slot_id,
+    )
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use crate::utils::db::testing::{create_slot, create_user};
+
+    use super::*;
+
+    async fn play_count(pool: &PgPool, slot_id: i64) -> i64 {
+        sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM played_slots WHERE slot_id = $1", slot_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .count
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn enter_then_play_counts_once(pool: PgPool) {
+        let user = create_user(&pool).await;
+        let slot_id = create_slot(&pool, user).await;
+
+        assert!(insert_play(&pool, user, slot_id, GameVersion::Lbp2, PlaySource::EnterLevel).await.unwrap());
+        assert!(!insert_play(&pool, user, slot_id, GameVersion::Lbp2, PlaySource::Play).await.unwrap());
+        assert_eq!(play_count(&pool, slot_id).await, 1);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn restart_inside_window_still_counts(pool: PgPool) {
+        let user = create_user(&pool).await;
+        let slot_id = create_slot(&pool, user).await;
+
+        for _ in 0..2 {
+            assert!(insert_play(&pool, user, slot_id, GameVersion::Lbp2, PlaySource::EnterLevel).await.unwrap());
+            assert!(!insert_play(&pool, user, slot_id, GameVersion::Lbp2, PlaySource::Play).await.unwrap());
+        }
+        assert_eq!(play_count(&pool, slot_id).await, 2);
+
+        // games that only report through /play
+        assert!(insert_play(&pool, user, slot_id, GameVersion::Lbp1, PlaySource::Play).await.unwrap());
+        assert!(insert_play(&pool, user, slot_id, GameVersion::Lbp1, PlaySource::Play).await.unwrap());
+        assert_eq!(play_count(&pool, slot_id).await, 4);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn other_players_and_later_plays_still_count(pool: PgPool) {
+        let user = create_user(&pool).await;
+        let other = create_user(&pool).await;
+        let slot_id = create_slot(&pool, other).await;
+
+        assert!(insert_play(&pool, user, slot_id, GameVersion::Lbp2, PlaySource::EnterLevel).await.unwrap());
+        assert!(insert_play(&pool, other, slot_id, GameVersion::Lbp2, PlaySource::Play).await.unwrap());
+
+        sqlx::query!("UPDATE played_slots SET timestamp = timestamp - interval '1 minute' WHERE slot_id = $1", slot_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(insert_play(&pool, user, slot_id, GameVersion::Lbp2, PlaySource::Play).await.unwrap());
+        assert_eq!(play_count(&pool, slot_id).await, 3);
+    }
+
+    async fn completion_count(pool: &PgPool, slot_id: i64) -> i64 {
+        sqlx::query!("SELECT COUNT(*) AS \"count!\" FROM played_slots WHERE slot_id = $1 AND completed", slot_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .count
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn completion_marks_one_play(pool: PgPool) {
+        let user = create_user(&pool).await;
+        let slot_id = create_slot(&pool, user).await;
+
+        // nothing to complete without a play
+        record_completion(&pool, user, slot_id).await.unwrap();
+        assert_eq!(completion_count(&pool, slot_id).await, 0);
+
+        insert_play(&pool, user, slot_id, GameVersion::Lbp2, PlaySource::EnterLevel).await.unwrap();
+        insert_play(&pool, user, slot_id, GameVersion::Lbp2, PlaySource::EnterLevel).await.unwrap();
+        record_completion(&pool, user, slot_id).await.unwrap();
+        assert_eq!(completion_count(&pool, slot_id).await, 1);
+        record_completion(&pool, user, slot_id).await.unwrap();
+        assert_eq!(completion_count(&pool, slot_id).await, 2);
+        record_completion(&pool, user, slot_id).await.unwrap();
+        assert_eq!(completion_count(&pool, slot_id).await, 2);
+    }
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
-use std::fs;
+use std::{fs, io::{self, Cursor}};
+
+use anyhow::Context;
 
 use axum::{
     Router,
@@ -6,7 +8,8 @@ use axum::{
     extract::{Path, State},
     response::{IntoResponse, Response},
     http::{StatusCode, HeaderValue},
-    body::{Bytes, Body}
+    body::{Bytes, Body},
+    Extension,
 };
 use tokio::fs::File;
 use tokio_util::io::ReaderStream;
@@ -17,7 +20,7 @@ use serde::Deserialize;
 use serde_with::serde_as;
 use sha1::{Digest, Sha1};
 
-use crate::{utils::resource::{get_hash_path, str_to_hash}, AppState, extractors::Xml};
+use crate::{utils::resource::{get_hash_path, str_to_hash}, AppState, extractors::Xml, types::{ResourceInfo, SessionData}};
 
 pub fn routes(resource_size_limit: u32) -> Router<AppState> {
     Router::new()
@@ -51,6 +54,7 @@ async fn download(Path(hash): Path<String>, State(state): State<AppState>) -> Re
 async fn upload(
     Path(hash): Path<String>,
     State(state): State<AppState>,
+    session: Extension<SessionData>,
     payload: Bytes,
 ) -> Result<impl IntoResponse, Response> {
     let hash = str_to_hash(&hash).map_err(|_| {
@@ -64,14 +68,37 @@ async fn upload(
         return Err((StatusCode::BAD_REQUEST, "Actual resource hash doesn't match hash in request").into_response());
     }
 
-    // TODO: add more checks n shit
-
     let path = get_hash_path(&state.config.resource_dir, hash);
 
+    // cheap enough to do before parsing and decompressing anything
     if path.exists() {
         return Err((StatusCode::CONFLICT, "Resource is already uploaded").into_response());
     }
 
+    let info = ResourceInfo::parse_from_res(&payload).map_err(|e| {
+        (StatusCode::BAD_REQUEST, format!("Invalid resource: {e}")).into_response()
+    })?;
+    if !info.res_type.is_allowed_in(session.game_version) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("{} resources can't be uploaded from {}", info.res_type, session.game_version),
+        ).into_response());
+    }
+    debug!(
+        "Uploading {} resource, revision {:?}, branch {:?}, compression flags {:?}, compressed {:?}, {} dependencies",
+        info.res_type, info.revision, info.branch, info.compression_flags, info.is_compressed, info.dependencies.len(),
+    );
+
+    // decompressing it all is the only way to know the data isn't corrupt, and it's too slow for the async runtime
+    let data = payload.clone();
+    tokio::task::spawn_blocking(move || {
+        info.body(Cursor::new(&data[..]))
+            .and_then(|mut body| io::copy(&mut body, &mut io::sink()).context("Couldn't decompress resource"))
+    })
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid resource: {e:#}")).into_response())?;
+
     fs::create_dir_all(&state.config.resource_dir).map_err(|e| {
         (StatusCode::INTERNAL_SERVER_ERROR, format!("Couldn't create resource dir: {e}")).into_response()
     })?;
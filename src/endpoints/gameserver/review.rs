@@ -0,0 +1,252 @@
+use axum::{Router, routing::{get, post}, extract::{Path, State}, http::StatusCode, response::{IntoResponse, Response}, Extension};
+use axum_extra::extract::Query;
+use futures::TryStreamExt;
+use maud::html as xml;
+use serde::Deserialize;
+use sqlx::{types::chrono::NaiveDateTime, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    extractors::Xml,
+    types::{GameVersion, Label, SessionData},
+    utils::db::{check_slot, check_slot_visibility, check_user_visibility, db_error, get_id_from_username},
+    AppState,
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/postReview/user/:id", post(post_review))
+        .route("/reviewsFor/user/:id", get(reviews_for))
+        .route("/reviewsBy/:online_id", get(reviews_by))
+        .route("/rateReview/user/:id/:online_id", post(rate_review))
+}
+
+#[derive(Deserialize)]
+struct PostReviewPayload {
+    #[serde(default)]
+    text: String,
+    // comma separated, e.g. "LABEL_Fun,LABEL_Short"
+    #[serde(default)]
+    labels: String,
+}
+
+async fn post_review(
+    Path(slot_id): Path<i64>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Xml<PostReviewPayload>,
+) -> Result<impl IntoResponse, Response> {
+    if matches!(session.game_version, GameVersion::Lbp1) {
+        return Err((StatusCode::BAD_REQUEST, "Reviews aren't used in LBP1").into_response());
+    }
+    check_slot(slot_id, &state).await?;
+    check_slot_visibility(slot_id, &session, &state).await?;
+
+    // the column is varchar(512), longer text would only fail in the database
+    if payload.text.chars().count() > 512 {
+        return Err((StatusCode::BAD_REQUEST, "Review text is too long").into_response());
+    }
+
+    let has_played = sqlx::query!(
+        "SELECT EXISTS(SELECT id FROM played_slots WHERE user_id = $1 AND slot_id = $2)",
+        session.user_id,
+        slot_id,
+    )
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?
+        .exists
+        .unwrap();
+
+    if !has_played {
+        return Err((StatusCode::BAD_REQUEST, "Cannot review a slot that hasn't been played").into_response());
+    }
+
+    // unknown labels are dropped rather than failing the whole review
+    let labels: Vec<String> = payload.labels.split(',')
+        .map(str::trim)
+        .filter(|l| Label::parse(l).is_some())
+        .map(String::from)
+        .collect();
+
+    // posting again replaces the previous review
+    sqlx::query!(
+        "INSERT INTO reviews (slot_id, author, content, labels) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (slot_id, author) DO UPDATE
+        SET content = EXCLUDED.content, labels = EXCLUDED.labels, posted_at = CURRENT_TIMESTAMP",
+        slot_id,
+        session.user_id,
+        state.text_filter.censor(&payload.text),
+        &labels,
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+struct RateReviewQuery {
+    rating: i16,
+}
+
+async fn rate_review(
+    Path((slot_id, online_id)): Path<(i64, String)>,
+    query: Query<RateReviewQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    if !(-1..=1).contains(&query.rating) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid rating").into_response());
+    }
+
+    let review = sqlx::query!(
+        "SELECT reviews.id, reviews.author FROM reviews
+        JOIN users ON reviews.author = users.id
+        WHERE reviews.slot_id = $1 AND users.online_id = $2",
+        slot_id,
+        online_id,
+    )
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Review not found").into_response())?;
+
+    if review.author == session.user_id {
+        return Err((StatusCode::BAD_REQUEST, "Cannot rate your own review").into_response());
+    }
+
+    sqlx::query!(
+        "INSERT INTO rated_reviews (user_id, review_id, rating) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, review_id) DO UPDATE
+        SET rating = EXCLUDED.rating, timestamp = CURRENT_TIMESTAMP",
+        session.user_id,
+        review.id,
+        query.rating,
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewListQuery {
+    page_start: i64,
+    page_size: i64,
+}
+
+enum ReviewFilter {
+    Slot(i64),
+    By(Uuid),
+}
+
+#[derive(sqlx::FromRow)]
+struct Review {
+    id: i64,
+    slot_id: i64,
+    author_oid: String,
+    content: String,
+    labels: Vec<String>,
+    posted_at: NaiveDateTime,
+    thumb: i16,
+    thumbsup: i64,
+    thumbsdown: i64,
+    your_thumb: i16,
+}
+
+async fn reviews_for(
+    Path(slot_id): Path<i64>,
+    query: Query<ReviewListQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    check_slot(slot_id, &state).await?;
+    check_slot_visibility(slot_id, &session, &state).await?;
+    reviews(ReviewFilter::Slot(slot_id), query, session, state).await
+}
+
+async fn reviews_by(
+    Path(online_id): Path<String>,
+    query: Query<ReviewListQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let user_id = get_id_from_username(&online_id, &state).await?;
+    check_user_visibility(user_id, &session, &state).await?;
+    reviews(ReviewFilter::By(user_id), query, session, state).await
+}
+
+async fn reviews(
+    filter: ReviewFilter,
+    params: Query<ReviewListQuery>,
+    session: Extension<SessionData>,
+    state: AppState,
+) -> Result<impl IntoResponse, Response> {
+    // the thumb shown on a review is the reviewer's own rating of the slot
+    let mut query = QueryBuilder::new(
+        "SELECT reviews.id, reviews.slot_id, reviews.content, reviews.labels, reviews.posted_at,
+        author.online_id AS author_oid,
+        COALESCE(slot_rating.rating, 0)::int2 AS thumb,
+        (SELECT COUNT(*) FROM rated_reviews WHERE review_id = reviews.id AND rating = 1) AS thumbsup,
+        (SELECT COUNT(*) FROM rated_reviews WHERE review_id = reviews.id AND rating = -1) AS thumbsdown,"
+    );
+    query.push(" COALESCE((SELECT rating FROM rated_reviews WHERE review_id = reviews.id AND user_id = ");
+    query.push_bind(session.user_id);
+    query.push("), 0)::int2 AS your_thumb");
+    query.push(
+        " FROM reviews
+        JOIN users author ON reviews.author = author.id
+        JOIN slots ON reviews.slot_id = slots.id
+        LEFT JOIN users slot_author ON slots.author = slot_author.id
+        LEFT JOIN rated_slots slot_rating ON reviews.slot_id = slot_rating.slot_id AND reviews.author = slot_rating.user_id"
+    );
+    match filter {
+        ReviewFilter::Slot(slot_id) => {
+            query.push(" WHERE reviews.slot_id = ");
+            query.push_bind(slot_id);
+        }
+        ReviewFilter::By(user_id) => {
+            query.push(" WHERE reviews.author = ");
+            query.push_bind(user_id);
+            // the slots aren't checked one by one here, so hidden levels have to be left out like in the stream
+            query.push(" AND (slot_author.id IS NULL OR slot_author.level_visibility != 'myself' OR slot_author.id = ");
+            query.push_bind(session.user_id);
+            query.push(") AND (slots.hidden = FALSE OR slots.author = ");
+            query.push_bind(session.user_id);
+            query.push(')');
+        }
+    }
+
+    query.push(" ORDER BY reviews.posted_at DESC");
+    query.push(" LIMIT ");
+    query.push_bind(params.page_size);
+    query.push(" OFFSET ");
+    query.push_bind(params.page_start - 1);
+
+    let mut reviews = query.build_query_as::<Review>()
+        .fetch(&state.pool);
+
+    Ok(Xml(xml!(
+        reviews {
+            @while let Some(review) = reviews.try_next().await.map_err(db_error)? {
+                review {
+                    id { (review.id) }
+                    slot_id type="user" { (review.slot_id) }
+                    reviewer { (review.author_oid) }
+                    timestamp { (review.posted_at.timestamp_millis()) }
+                    labels { (review.labels.join(",")) }
+                    deleted { "false" }
+                    text { (review.content) }
+                    thumb { (review.thumb) }
+                    thumbsup { (review.thumbsup) }
+                    thumbsdown { (review.thumbsdown) }
+                    yourthumb { (review.your_thumb) }
+                }
+            }
+        }
+    )))
+}
\ No newline at end of file
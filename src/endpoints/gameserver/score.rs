@@ -0,0 +1,243 @@
+use axum::{Router, routing::{get, post}, extract::{Path, State}, http::StatusCode, response::{IntoResponse, Response}, Extension};
+use axum_extra::extract::Query;
+use maud::{html as xml, Markup};
+use serde::Deserialize;
+use tower_sessions::Session;
+
+use crate::{extractors::Xml, types::SessionData, AppState, utils::db::db_error};
+
+use super::{find_slot_id, get_slot_id, relation::record_completion, SlotType};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/scoreboard/:slot_type/:id", get(scoreboards))
+        .route("/scoreboard/:slot_type/:id", post(submit_score))
+        .route("/scoreboard/friends/:slot_type/:id", get(friend_scoreboards))
+        .route("/topscores/:slot_type/:id/:player_count", get(top_scores))
+}
+
+// amount of scores shown per player count on the lbp1 scoreboards
+const SCOREBOARD_SIZE: i64 = 10;
+// page size the game uses when it doesn't specify one
+const DEFAULT_PAGE_SIZE: i64 = 5;
+// so one request can't pull the whole scoreboard
+const MAX_PAGE_SIZE: i64 = 100;
+
+struct Score {
+    players: Vec<String>,
+    // whoever submitted the score, the order of players isn't kept
+    main_player: String,
+    score: i32,
+    rank: i64,
+    total: i64,
+}
+
+#[derive(Deserialize)]
+struct SubmitScorePayload {
+    #[serde(rename = "type")]
+    player_count: i16,
+    #[serde(rename = "playerIds")]
+    players: Vec<String>,
+    score: i32,
+}
+
+async fn submit_score(
+    Path((slot_type, id)): Path<(SlotType, i64)>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Xml<SubmitScorePayload>,
+) -> Result<impl IntoResponse, Response> {
+    if !(1..=4).contains(&payload.player_count) || payload.players.len() != payload.player_count as usize {
+        return Err((StatusCode::BAD_REQUEST, "Invalid player count").into_response());
+    }
+    if !payload.players.contains(&session.online_id) {
+        return Err((StatusCode::BAD_REQUEST, "User isn't one of the players").into_response());
+    }
+
+    let slot_id = get_slot_id(slot_type, id, &session, &state).await?;
+
+    // the host's position in the list changes between games, but it's still the same group
+    let mut players = payload.players.clone();
+    players.sort();
+
+    sqlx::query!(
+        "INSERT INTO scores (slot_id, user_id, player_count, players, score) VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (slot_id, player_count, players) DO UPDATE
+        SET user_id = EXCLUDED.user_id, score = EXCLUDED.score, submitted_at = CURRENT_TIMESTAMP
+        WHERE scores.score < EXCLUDED.score",
+        slot_id,
+        session.user_id,
+        payload.player_count,
+        &players,
+        payload.score,
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    record_completion(&state.pool, session.user_id, slot_id).await.map_err(db_error)?;
+
+    Ok(Xml(score_segment(Some(slot_id), payload.player_count, None, DEFAULT_PAGE_SIZE, &session, &state).await?))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TopScoresQuery {
+    page_start: Option<i64>,
+    page_size: Option<i64>,
+}
+
+async fn top_scores(
+    Path((slot_type, id, player_count)): Path<(SlotType, i64, i16)>,
+    query: Query<TopScoresQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    if query.page_start.is_some_and(|p| p < 1) {
+        return Err((StatusCode::BAD_REQUEST, "Invalid page start").into_response());
+    }
+    let slot_id = find_slot_id(slot_type, id, &state).await?;
+    let page_size = query.page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    Ok(Xml(score_segment(slot_id, player_count, query.page_start, page_size, &session, &state).await?))
+}
+
+async fn scoreboards(
+    Path((slot_type, id)): Path<(SlotType, i64)>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    let slot_id = find_slot_id(slot_type, id, &state).await?;
+    Ok(Xml(multi_scoreboard(slot_id, None, &state).await?))
+}
+
+async fn friend_scoreboards(
+    Path((slot_type, id)): Path<(SlotType, i64)>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    session_store: Session,
+) -> Result<impl IntoResponse, Response> {
+    let slot_id = find_slot_id(slot_type, id, &state).await?;
+
+    let mut players: Vec<String> = session_store.get("friends")
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .unwrap_or_default();
+    players.push(session.online_id.clone());
+
+    Ok(Xml(multi_scoreboard(slot_id, Some(players), &state).await?))
+}
+
+// without a page start, the page is centered around the user's best score.
+// slot_id is None for developer slots without a row, which can't have any scores yet
+async fn score_segment(
+    slot_id: Option<i64>,
+    player_count: i16,
+    page_start: Option<i64>,
+    page_size: i64,
+    session: &SessionData,
+    state: &AppState,
+) -> Result<Markup, Response> {
+    let own_score = sqlx::query!(
+        "SELECT score, rank FROM (
+            SELECT players, score, RANK() OVER (ORDER BY score DESC) AS rank
+            FROM scores WHERE slot_id = $1 AND player_count = $2
+        ) AS ranked
+        WHERE $3 = ANY(players)
+        ORDER BY rank LIMIT 1",
+        slot_id,
+        player_count,
+        session.online_id,
+    )
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    let (own_score, own_rank) = own_score.map_or((0, 0), |s| (s.score, s.rank.unwrap_or_default()));
+    let offset = match page_start {
+        Some(page_start) => page_start - 1,
+        None => (own_rank - 1 - page_size / 2).max(0),
+    };
+
+    let scores = sqlx::query_as!(
+        Score,
+        "SELECT players, users.online_id AS main_player, score,
+        RANK() OVER (ORDER BY score DESC) AS \"rank!\",
+        COUNT(*) OVER() AS \"total!\"
+        FROM scores JOIN users ON scores.user_id = users.id
+        WHERE slot_id = $1 AND player_count = $2
+        ORDER BY score DESC, submitted_at
+        LIMIT $3 OFFSET $4",
+        slot_id,
+        player_count,
+        page_size,
+        offset,
+    )
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    let total = scores.first().map_or(0, |s| s.total);
+
+    Ok(xml!(
+        scoreboardSegment totalNumScores=(total) yourScore=(own_score) yourRank=(own_rank) {
+            (play_records(&scores))
+        }
+    ))
+}
+
+// lbp1 shows the top scores for every player count at once
+async fn multi_scoreboard(
+    slot_id: Option<i64>,
+    players: Option<Vec<String>>,
+    state: &AppState,
+) -> Result<Markup, Response> {
+    let mut scoreboards = Vec::new();
+    for player_count in 1..=4 {
+        let scores = sqlx::query_as!(
+            Score,
+            "SELECT players, users.online_id AS main_player, score,
+            RANK() OVER (ORDER BY score DESC) AS \"rank!\",
+            COUNT(*) OVER() AS \"total!\"
+            FROM scores JOIN users ON scores.user_id = users.id
+            WHERE slot_id = $1 AND player_count = $2
+            AND ($3::varchar[] IS NULL OR players && $3::varchar[])
+            ORDER BY score DESC, submitted_at
+            LIMIT $4",
+            slot_id,
+            player_count,
+            players.as_deref(),
+            SCOREBOARD_SIZE,
+        )
+            .fetch_all(&state.pool)
+            .await
+            .map_err(db_error)?;
+
+        scoreboards.push((player_count, scores));
+    }
+
+    Ok(xml!(
+        scoreboards {
+            @for (player_count, scores) in &scoreboards {
+                topScores players=(player_count) {
+                    (play_records(scores))
+                }
+            }
+        }
+    ))
+}
+
+fn play_records(scores: &[Score]) -> Markup {
+    xml!(
+        @for score in scores {
+            playRecord {
+                type { (score.players.len()) }
+                @for player in &score.players {
+                    playerIds { (player) }
+                }
+                mainPlayer { (score.main_player) }
+                rank { (score.rank) }
+                score { (score.score) }
+            }
+        }
+    )
+}
@@ -1,4 +1,4 @@
-use axum::{Router, routing::get, extract::{State, Path}, Extension, response::{IntoResponse, Response}};
+use axum::{Router, routing::get, extract::{State, Path}, Extension, http::StatusCode, response::{IntoResponse, Response}};
 use axum_extra::extract::Query;
 use chrono::NaiveDateTime;
 use maud::html as xml;
@@ -6,14 +6,27 @@ use serde::Deserialize;
 use sqlx::QueryBuilder;
 use uuid::Uuid;
 
-use crate::{extractors::Xml, types::SessionData, AppState, utils::db::{db_error, get_id_from_username}};
+use crate::{extractors::Xml, types::{GameVersion, SessionData}, AppState, utils::db::{check_playlist_visibility, db_error, get_id_from_username}};
+
+use super::{developer_slot, tags::Tags, DeveloperSlotCounts};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/slots", get(slots_newest))
         .route("/slots/by", get(slots_by))
+        .route("/slots/search", get(slots_text_search))
+        .route("/slots/mmpicks", get(slots_team_picks))
+        // lbp1 calls team picks "cool levels"
+        .route("/slots/cool", get(slots_team_picks))
+        .route("/slots/mostHearted", get(slots_most_hearted))
+        .route("/slots/highestRated", get(slots_highest_rated))
+        .route("/slots/mostPlayed", get(slots_most_played))
+        .route("/slots/thumbs", get(slots_most_thumbs))
+        .route("/slots/lbp2luckydip", get(slots_lucky_dip))
+        .route("/slots/tag", get(slots_by_tag))
         .route("/favouriteSlots/:username", get(favourite_slots))
         .route("/slots/lolcatftw/:username", get(queued_slots))
+        .route("/playlists/:id/slots", get(playlist_slots))
 }
 
 #[derive(Deserialize)]
@@ -21,20 +34,77 @@ pub fn routes() -> Router<AppState> {
 struct SlotSearchQuery {
     page_start: i64,
     page_size: i64,
-    // TODO: game_filter_type: Option<String>,
+    game_filter_type: Option<GameFilterType>,
+    date_filter_type: Option<DateFilterType>,
+    players: Option<i16>,
+    #[serde(rename = "move")]
+    move_filter: Option<MoveFilter>,
+    label_filter0: Option<String>,
+    label_filter1: Option<String>,
+    label_filter2: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum GameFilterType {
+    Lbp1,
+    Lbp2,
+    Lbp3,
+    // any game the client can play
+    Both,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum DateFilterType {
+    ThisWeek,
+    ThisMonth,
+    AllTime,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum MoveFilter {
+    #[serde(rename = "true")]
+    Allowed,
+    #[serde(rename = "false", alias = "noneCan")]
+    Excluded,
+    #[serde(alias = "allMust")]
+    Only,
 }
 
 #[derive(sqlx::FromRow)]
 struct Slot {
     id: i64,
+    story_id: Option<i64>,
     name: String,
-    author_name: String,
+    author_name: Option<String>,
     gamever: i16,
     heart_count: i64,
+    thumbsup: i64,
+    thumbsdown: i64,
+    average_rating: f64,
+    your_rating: i16,
+    your_lbp1_rating: i16,
+    lbp1_play_count: i64,
+    lbp1_completion_count: i64,
+    lbp1_unique_play_count: i64,
+    lbp2_play_count: i64,
+    lbp2_completion_count: i64,
+    lbp2_unique_play_count: i64,
+    lbp3_play_count: i64,
+    lbp3_completion_count: i64,
+    lbp3_unique_play_count: i64,
+    your_lbp1_play_count: i64,
+    your_lbp2_play_count: i64,
+    review_count: i64,
+    comment_count: i64,
+    photo_count: i64,
+    top_tags: Vec<String>,
     mmpicked_at: Option<NaiveDateTime>,
     description: String,
     icon: String,
-    root_level: String,
+    root_level: Option<String>,
     resources: Vec<String>,
     location_x: i32,
     location_y: i32,
@@ -43,7 +113,7 @@ struct Slot {
     is_lbp1_only: bool,
     shareable: bool,
     // TODO: level_type: String,
-    //labels: Option<Vec<String>>,
+    labels: Vec<String>,
     // TODO: move_required: bool,
     // TODO: vita_cc_required: bool,
 
@@ -55,6 +125,15 @@ enum SlotSearchFilter {
     UploadedBy(Uuid),
     LastHeartedBy(Uuid),
     LastQueuedBy(Uuid),
+    Text(String),
+    TeamPicks,
+    MostHearted,
+    HighestRated,
+    MostPlayed,
+    MostThumbs,
+    LuckyDip(i64),
+    InPlaylist(i64),
+    Tag(String),
 }
 
 async fn slots_newest(
@@ -69,6 +148,107 @@ async fn slots_newest(
     ).await
 }
 
+async fn slots_team_picks(
+    query: Query<SlotSearchQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    slot_search(
+        state, session,
+        SlotSearchFilter::TeamPicks,
+        query
+    ).await
+}
+
+async fn slots_most_hearted(
+    query: Query<SlotSearchQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    slot_search(
+        state, session,
+        SlotSearchFilter::MostHearted,
+        query
+    ).await
+}
+
+async fn slots_highest_rated(
+    query: Query<SlotSearchQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    slot_search(
+        state, session,
+        SlotSearchFilter::HighestRated,
+        query
+    ).await
+}
+
+async fn slots_most_played(
+    query: Query<SlotSearchQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    slot_search(
+        state, session,
+        SlotSearchFilter::MostPlayed,
+        query
+    ).await
+}
+
+async fn slots_most_thumbs(
+    query: Query<SlotSearchQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    slot_search(
+        state, session,
+        SlotSearchFilter::MostThumbs,
+        query
+    ).await
+}
+
+#[derive(Deserialize)]
+struct LuckyDipQuery {
+    #[serde(default)]
+    seed: i64,
+}
+
+// the game sends the same seed for every page, so the order stays the same while paging
+async fn slots_lucky_dip(
+    query: Query<SlotSearchQuery>,
+    query2: Query<LuckyDipQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    slot_search(
+        state, session,
+        SlotSearchFilter::LuckyDip(query2.seed),
+        query
+    ).await
+}
+
+#[derive(Deserialize)]
+struct TagQuery {
+    tag: String,
+}
+
+async fn slots_by_tag(
+    query: Query<SlotSearchQuery>,
+    query2: Query<TagQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    if Tags::parse(&query2.tag).is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Invalid tag").into_response());
+    }
+    slot_search(
+        state, session,
+        SlotSearchFilter::Tag(query2.0.tag),
+        query
+    ).await
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SlotsByQuery {
@@ -89,6 +269,34 @@ async fn slots_by(
     ).await
 }
 
+#[derive(Deserialize)]
+struct TextSearchQuery {
+    query: String,
+}
+
+async fn slots_text_search(
+    query: Query<SlotSearchQuery>,
+    query2: Query<TextSearchQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    slot_search(
+        state, session,
+        SlotSearchFilter::Text(query2.0.query),
+        query
+    ).await
+}
+
+// every word has to be the start of a word in the level, so "mario" finds "MarioCastle".
+// only letters and digits are kept, which also keeps tsquery operators out
+fn prefix_tsquery(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("{w}:*"))
+        .collect::<Vec<String>>()
+        .join(" & ")
+}
+
 async fn favourite_slots(
     query: Query<SlotSearchQuery>,
     State(state): State<AppState>,
@@ -117,6 +325,20 @@ async fn queued_slots(
     ).await
 }
 
+async fn playlist_slots(
+    query: Query<SlotSearchQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    Path(playlist_id): Path<i64>,
+) -> Result<impl IntoResponse, Response> {
+    check_playlist_visibility(playlist_id, &session, &state).await?;
+    slot_search(
+        state, session,
+        SlotSearchFilter::InPlaylist(playlist_id),
+        query
+    ).await
+}
+
 async fn slot_search(
     state: AppState,
     session: Extension<SessionData>,
@@ -128,11 +350,42 @@ async fn slot_search(
         "SELECT slots.*,
         users.online_id AS author_name,
         count(DISTINCT hearts.user_id) AS heart_count,
-        count(slots.id) OVER() AS total
+        (SELECT count(*) FROM rated_slots WHERE slot_id = slots.id AND rating = 1) AS thumbsup,
+        (SELECT count(*) FROM rated_slots WHERE slot_id = slots.id AND rating = -1) AS thumbsdown,
+        (SELECT COALESCE(AVG(lbp1_rating), 0)::float8 FROM rated_slots WHERE slot_id = slots.id) AS average_rating,
+        COALESCE(own_rating.rating, 0)::int2 AS your_rating,
+        COALESCE(own_rating.lbp1_rating, 0)::int2 AS your_lbp1_rating,
+        (SELECT count(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 0) AS lbp1_play_count,
+        (SELECT count(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 0 AND completed) AS lbp1_completion_count,
+        (SELECT count(DISTINCT user_id) FROM played_slots WHERE slot_id = slots.id AND gamever = 0) AS lbp1_unique_play_count,
+        (SELECT count(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 1) AS lbp2_play_count,
+        (SELECT count(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 1 AND completed) AS lbp2_completion_count,
+        (SELECT count(DISTINCT user_id) FROM played_slots WHERE slot_id = slots.id AND gamever = 1) AS lbp2_unique_play_count,
+        (SELECT count(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 2) AS lbp3_play_count,
+        (SELECT count(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 2 AND completed) AS lbp3_completion_count,
+        (SELECT count(DISTINCT user_id) FROM played_slots WHERE slot_id = slots.id AND gamever = 2) AS lbp3_unique_play_count,
+        (SELECT count(*) FROM reviews WHERE slot_id = slots.id) AS review_count,
+        (SELECT count(*) FROM comments WHERE target_slot = slots.id) AS comment_count,
+        (SELECT count(*) FROM photos WHERE slot_id = slots.id) AS photo_count,
+        ARRAY(
+            SELECT tag FROM slot_tags WHERE slot_id = slots.id
+            GROUP BY tag ORDER BY count(*) DESC, tag LIMIT 3
+        ) AS top_tags,"
+    );
+    sql.push(" (SELECT count(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 0 AND user_id = ");
+    sql.push_bind(session.user_id);
+    sql.push(") AS your_lbp1_play_count,");
+    sql.push(" (SELECT count(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 1 AND user_id = ");
+    sql.push_bind(session.user_id);
+    sql.push(") AS your_lbp2_play_count,");
+    sql.push(
+        " count(slots.id) OVER() AS total
         FROM slots
-        JOIN users ON slots.author = users.id
+        LEFT JOIN users ON slots.author = users.id
         LEFT JOIN favourite_slots AS hearts ON slots.id = hearts.slot_id"
     );
+    sql.push(" LEFT JOIN rated_slots AS own_rating ON slots.id = own_rating.slot_id AND own_rating.user_id = ");
+    sql.push_bind(session.user_id);
 
     if let SlotSearchFilter::LastHeartedBy(user_id) = filter {
         sql.push(" JOIN favourite_slots AS own_hearts ON slots.id = own_hearts.slot_id AND own_hearts.user_id = ");
@@ -142,17 +395,79 @@ async fn slot_search(
         sql.push(" JOIN queued_slots AS own_queues ON slots.id = own_queues.slot_id AND own_queues.user_id = ");
         sql.push_bind(user_id);
     }
+    if let SlotSearchFilter::InPlaylist(playlist_id) = filter {
+        sql.push(" JOIN playlist_slots AS playlist ON slots.id = playlist.slot_id AND playlist.playlist_id = ");
+        sql.push_bind(playlist_id);
+    }
 
     sql.push(" WHERE gamever <= ");
     sql.push_bind(session.game_version as i16);
     sql.push(" AND (is_sub_level = FALSE OR author = ");
     sql.push_bind(session.user_id);
     sql.push(')');
+    // developer slots don't have an author, so there's no visibility setting to check
+    sql.push(" AND (author IS NULL OR users.level_visibility != 'myself' OR author = ");
+    sql.push_bind(session.user_id);
+    sql.push(')');
+    sql.push(" AND (slots.hidden = FALSE OR author = ");
+    sql.push_bind(session.user_id);
+    sql.push(')');
+    // developer slots only get a row once someone interacts with them, so they only make sense in hearts and queues
+    if !matches!(filter, SlotSearchFilter::LastHeartedBy(_) | SlotSearchFilter::LastQueuedBy(_)) {
+        sql.push(" AND story_id IS NULL");
+    }
+    match query.game_filter_type {
+        Some(GameFilterType::Lbp1) => { sql.push(" AND gamever = 0"); },
+        Some(GameFilterType::Lbp2) => { sql.push(" AND gamever = 1"); },
+        Some(GameFilterType::Lbp3) => { sql.push(" AND gamever = 2"); },
+        Some(GameFilterType::Both) | None => {},
+    }
+    match query.date_filter_type {
+        Some(DateFilterType::ThisWeek) => { sql.push(" AND published_at >= CURRENT_TIMESTAMP - INTERVAL '1 week'"); },
+        Some(DateFilterType::ThisMonth) => { sql.push(" AND published_at >= CURRENT_TIMESTAMP - INTERVAL '1 month'"); },
+        Some(DateFilterType::AllTime) | None => {},
+    }
+    if let Some(players) = query.players {
+        sql.push(" AND max_players >= ");
+        sql.push_bind(players);
+    }
+    match query.move_filter {
+        Some(MoveFilter::Excluded) => { sql.push(" AND move_required = FALSE"); },
+        Some(MoveFilter::Only) => { sql.push(" AND move_required = TRUE"); },
+        Some(MoveFilter::Allowed) | None => {},
+    }
+    let labels: Vec<&String> = [&query.label_filter0, &query.label_filter1, &query.label_filter2]
+        .into_iter()
+        .flatten()
+        .filter(|l| !l.is_empty())
+        .collect();
+    if !labels.is_empty() {
+        sql.push(" AND slots.labels @> ");
+        sql.push_bind(labels.into_iter().cloned().collect::<Vec<String>>());
+        sql.push("::varchar[]");
+    }
     if let SlotSearchFilter::UploadedBy(user_id) = filter {
         sql.push(" AND author = ");
         sql.push_bind(user_id);
     }
-    sql.push(" GROUP BY slots.id, author_name");
+    if let SlotSearchFilter::Tag(ref tag) = filter {
+        sql.push(" AND EXISTS(SELECT tag FROM slot_tags WHERE slot_id = slots.id AND tag = ");
+        sql.push_bind(tag.clone());
+        sql.push(')');
+    }
+    if let SlotSearchFilter::TeamPicks = filter {
+        sql.push(" AND mmpicked_at IS NOT NULL");
+    }
+    // an OR across both would keep postgres from using slots_search_idx, so levels matching the
+    // text and levels by an author with exactly that online id are looked up separately
+    if let SlotSearchFilter::Text(ref text) = filter {
+        sql.push(" AND slots.id IN (SELECT id FROM slots WHERE to_tsvector('simple', name || ' ' || description) @@ to_tsquery('simple', ");
+        sql.push_bind(prefix_tsquery(text));
+        sql.push(") UNION SELECT id FROM slots WHERE author IN (SELECT id FROM users WHERE lower(online_id) = lower(");
+        sql.push_bind(text.trim().to_string());
+        sql.push(")))");
+    }
+    sql.push(" GROUP BY slots.id, author_name, own_rating.rating, own_rating.lbp1_rating");
 
     if let SlotSearchFilter::LastHeartedBy(_) = filter {
         sql.push(", own_hearts.timestamp");
@@ -160,13 +475,46 @@ async fn slot_search(
     if let SlotSearchFilter::LastQueuedBy(_) = filter {
         sql.push(", own_queues.timestamp");
     }
+    if let SlotSearchFilter::InPlaylist(_) = filter {
+        sql.push(", playlist.position");
+    }
 
     sql.push(" ORDER BY ");
+    if let SlotSearchFilter::Text(ref text) = filter {
+        sql.push("lower(users.online_id) = lower(");
+        sql.push_bind(text.trim().to_string());
+        sql.push(") DESC, ts_rank(to_tsvector('simple', slots.name || ' ' || slots.description), to_tsquery('simple', ");
+        sql.push_bind(prefix_tsquery(text));
+        sql.push(")) DESC, ");
+    }
+    if let SlotSearchFilter::Tag(ref tag) = filter {
+        sql.push("(SELECT count(*) FROM slot_tags WHERE slot_id = slots.id AND tag = ");
+        sql.push_bind(tag.clone());
+        sql.push(") DESC, ");
+    }
+    if let SlotSearchFilter::LuckyDip(seed) = filter {
+        sql.push("md5(slots.id::text || ");
+        sql.push_bind(seed);
+        sql.push("::text), ");
+    }
     sql.push(match filter {
         SlotSearchFilter::Newest => "published_at DESC",
         SlotSearchFilter::UploadedBy(_) => "published_at DESC",
         SlotSearchFilter::LastHeartedBy(_) => "own_hearts.timestamp DESC",
         SlotSearchFilter::LastQueuedBy(_) => "own_queues.timestamp DESC",
+        SlotSearchFilter::Text(_) => "published_at DESC",
+        SlotSearchFilter::TeamPicks => "mmpicked_at DESC",
+        SlotSearchFilter::MostHearted => "heart_count DESC, published_at DESC",
+        // lbp1 rates with stars, later games with thumbs
+        SlotSearchFilter::HighestRated => match session.game_version {
+            GameVersion::Lbp1 => "average_rating DESC, published_at DESC",
+            _ => "(SELECT COALESCE(SUM(rating), 0) FROM rated_slots WHERE slot_id = slots.id) DESC, published_at DESC",
+        },
+        SlotSearchFilter::MostPlayed => "(SELECT count(DISTINCT user_id) FROM played_slots WHERE slot_id = slots.id) DESC, published_at DESC",
+        SlotSearchFilter::MostThumbs => "thumbsup DESC, published_at DESC",
+        SlotSearchFilter::LuckyDip(_) => "slots.id",
+        SlotSearchFilter::InPlaylist(_) => "playlist.position",
+        SlotSearchFilter::Tag(_) => "published_at DESC",
     });
     sql.push(" LIMIT ");
     sql.push_bind(query.page_size);
@@ -183,45 +531,76 @@ async fn slot_search(
 
     let xml_list = xml!(
         @for slot in slots {
-            slot type="user" {
-                id { (slot.id) }
-                npHandle { (slot.author_name) }
-                location {
-                    x { (slot.location_x) }
-                    y { (slot.location_y) }
-                }
-                game { (slot.gamever) }
-                name { (slot.name) }
-                description { (slot.description) }
-                rootLevel { (slot.root_level) }
-                @for resource in slot.resources {
-                    resource { (resource) }
+            @if let Some(story_id) = slot.story_id {
+                (developer_slot(story_id, DeveloperSlotCounts {
+                    heart_count: slot.heart_count,
+                    thumbsup: slot.thumbsup,
+                    thumbsdown: slot.thumbsdown,
+                    average_rating: slot.average_rating,
+                    your_lbp1_rating: slot.your_lbp1_rating,
+                    your_rating: slot.your_rating,
+                    your_lbp1_play_count: slot.your_lbp1_play_count,
+                    your_lbp2_play_count: slot.your_lbp2_play_count,
+                    comment_count: slot.comment_count,
+                    photo_count: slot.photo_count,
+                    lbp1_play_count: slot.lbp1_play_count,
+                    lbp1_completion_count: slot.lbp1_completion_count,
+                    lbp1_unique_play_count: slot.lbp1_unique_play_count,
+                    lbp2_play_count: slot.lbp2_play_count,
+                    lbp2_completion_count: slot.lbp2_completion_count,
+                    lbp2_unique_play_count: slot.lbp2_unique_play_count,
+                    lbp3_play_count: slot.lbp3_play_count,
+                    lbp3_completion_count: slot.lbp3_completion_count,
+                    lbp3_unique_play_count: slot.lbp3_unique_play_count,
+                }))
+            } @else {
+                slot type="user" {
+                    id { (slot.id) }
+                    npHandle { (slot.author_name.unwrap_or_default()) }
+                    location {
+                        x { (slot.location_x) }
+                        y { (slot.location_y) }
+                    }
+                    game { (slot.gamever) }
+                    name { (slot.name) }
+                    description { (slot.description) }
+                    rootLevel { (slot.root_level.unwrap_or_default()) }
+                    @for resource in slot.resources {
+                        resource { (resource) }
+                    }
+                    icon { (slot.icon) }
+                    initiallyLocked { (slot.initially_locked) }
+                    isSubLevel { (slot.is_sub_level) }
+                    isLBP1Only { (slot.is_lbp1_only) }
+                    tags { (slot.top_tags.join(",")) } // lbp1
+                    shareable { (slot.shareable) }
+                    labels { (slot.labels.join(",")) }
+                    heartCount { (slot.heart_count) }
+                    thumbsup { (slot.thumbsup) }
+                    thumbsdown { (slot.thumbsdown) }
+                    averageRating { (slot.average_rating) } // lbp1
+                    playerCount { "0" }
+                    matchingPlayers { "0" }
+                    mmpick { (slot.mmpicked_at.is_some()) }
+                    yourRating { (slot.your_lbp1_rating) } // lbp1
+                    yourDPadRating { (slot.your_rating) } // lbp2+
+                    yourlbp1PlayCount { (slot.your_lbp1_play_count) }
+                    yourlbp2PlayCount { (slot.your_lbp2_play_count) }
+                    reviewCount { (slot.review_count) }
+                    isAdventurePlanet { "false" } // lbp3
+                    ps4Only { "false" } // lbp3
+                    playCount { (&(slot.lbp1_play_count + slot.lbp2_play_count + slot.lbp3_play_count)) } // all games
+                    completionCount { (&(slot.lbp1_completion_count + slot.lbp2_completion_count + slot.lbp3_completion_count)) } // all games
+                    lbp1PlayCount { (slot.lbp1_play_count) }
+                    lbp1CompletionCount { (slot.lbp1_completion_count) }
+                    lbp1UniquePlayCount { (slot.lbp1_unique_play_count) }
+                    lbp2PlayCount { (slot.lbp2_play_count) }
+                    lbp2CompletionCount { (slot.lbp2_completion_count) }
+                    uniquePlayCount { (slot.lbp2_unique_play_count) }
+                    lbp3PlayCount { (slot.lbp3_play_count) }
+                    lbp3CompletionCount { (slot.lbp3_completion_count) }
+                    lbp3UniquePlayCount { (slot.lbp3_unique_play_count) }
                 }
-                icon { (slot.icon) }
-                initiallyLocked { (slot.initially_locked) }
-                isSubLevel { (slot.is_sub_level) }
-                isLBP1Only { (slot.is_lbp1_only) }
-                shareable { (slot.shareable) }
-                heartCount { (slot.heart_count) }
-                thumbsup { "0" }
-                thumbsdown { "0" }
-                averageRating { "0.0" } // lbp1
-                playerCount { "0" }
-                matchingPlayers { "0" }
-                mmpick { (slot.mmpicked_at.is_some()) }
-                isAdventurePlanet { "false" } // lbp3
-                ps4Only { "false" } // lbp3
-                playCount { "0" } // all games
-                completionCount { "0" } // all games
-                lbp1PlayCount { "0" }
-                lbp1CompletionCount { "0" }
-                lbp1UniquePlayCount { "0" }
-                lbp2PlayCount { "0" }
-                lbp2CompletionCount { "0" }
-                uniquePlayCount { "0" }
-                lbp3PlayCount { "0" }
-                lbp3CompletionCount { "0" }
-                lbp3UniquePlayCount { "0" }
             }
         }
     );
@@ -1,7 +1,9 @@
-use axum::{routing::get, Router, http::StatusCode, response::{IntoResponse, Response}, extract::{State, Path}};
+use axum::{routing::get, Router, http::StatusCode, response::{IntoResponse, Response}, extract::{State, Path}, Extension};
 use maud::html as xml;
 
-use crate::{extractors::Xml, AppState, utils::db::db_error};
+use crate::{extractors::Xml, AppState, types::SessionData, utils::db::{db_error, check_slot_visibility}};
+
+use super::{developer_slot, find_slot_id, DeveloperSlotCounts, SlotType};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
@@ -9,33 +11,94 @@ pub fn routes() -> Router<AppState> {
 }
 
 async fn slot(
-    Path((_, id)): Path<(String, i64)>,
+    Path((slot_type, id)): Path<(SlotType, i64)>,
     State(state): State<AppState>,
+    session: Extension<SessionData>,
 ) -> Result<impl IntoResponse, Response> {
-    // TODO: add support for dev slots
+    let Some(slot_id) = find_slot_id(slot_type, id, &state).await? else {
+        // nobody has interacted with this developer slot yet, so there's nothing to count
+        return Ok(Xml(developer_slot(id, DeveloperSlotCounts::default())));
+    };
+    check_slot_visibility(slot_id, &session, &state).await?;
 
     // https://stackoverflow.com/a/26727307
     let slot = sqlx::query!(
-        "SELECT slots.*, author.online_id as author_oid,
+        "SELECT slots.*, author.online_id as \"author_oid?\",
         COUNT(DISTINCT comments.id) AS comment_count,
-        COUNT(DISTINCT hearts.user_id) AS heart_count
+        COUNT(DISTINCT hearts.user_id) AS heart_count,
+        (SELECT COUNT(*) FROM rated_slots WHERE slot_id = slots.id AND rating = 1) AS thumbsup,
+        (SELECT COUNT(*) FROM rated_slots WHERE slot_id = slots.id AND rating = -1) AS thumbsdown,
+        (SELECT AVG(lbp1_rating)::float8 FROM rated_slots WHERE slot_id = slots.id) AS average_rating,
+        own_rating.rating AS your_rating,
+        own_rating.lbp1_rating AS your_lbp1_rating,
+        (SELECT COUNT(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 0) AS lbp1_play_count,
+        (SELECT COUNT(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 0 AND completed) AS lbp1_completion_count,
+        (SELECT COUNT(DISTINCT user_id) FROM played_slots WHERE slot_id = slots.id AND gamever = 0) AS lbp1_unique_play_count,
+        (SELECT COUNT(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 1) AS lbp2_play_count,
+        (SELECT COUNT(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 1 AND completed) AS lbp2_completion_count,
+        (SELECT COUNT(DISTINCT user_id) FROM played_slots WHERE slot_id = slots.id AND gamever = 1) AS lbp2_unique_play_count,
+        (SELECT COUNT(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 2) AS lbp3_play_count,
+        (SELECT COUNT(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 2 AND completed) AS lbp3_completion_count,
+        (SELECT COUNT(DISTINCT user_id) FROM played_slots WHERE slot_id = slots.id AND gamever = 2) AS lbp3_unique_play_count,
+        (SELECT COUNT(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 0 AND user_id = $2) AS your_lbp1_play_count,
+        (SELECT COUNT(*) FROM played_slots WHERE slot_id = slots.id AND gamever = 1 AND user_id = $2) AS your_lbp2_play_count,
+        (SELECT COUNT(*) FROM reviews WHERE slot_id = slots.id) AS review_count,
+        ARRAY(
+            SELECT tag FROM slot_tags WHERE slot_id = slots.id
+            GROUP BY tag ORDER BY COUNT(*) DESC, tag LIMIT 3
+        ) AS \"top_tags!\",
+        (SELECT COUNT(*) FROM photos WHERE slot_id = slots.id) AS photo_count,
+        (SELECT COUNT(*) FROM photos WHERE slot_id = slots.id AND author = slots.author) AS author_photo_count
         FROM slots
-        JOIN users author ON slots.author = author.id
+        LEFT JOIN users author ON slots.author = author.id
         LEFT JOIN comments ON slots.id = comments.target_slot
         LEFT JOIN favourite_slots AS hearts ON slots.id = hearts.slot_id
+        LEFT JOIN rated_slots AS own_rating ON slots.id = own_rating.slot_id AND own_rating.user_id = $2
         WHERE slots.id = $1
-        GROUP BY slots.id, author_oid",
-        id
+        GROUP BY slots.id, author.online_id, own_rating.rating, own_rating.lbp1_rating",
+        slot_id,
+        session.user_id,
     )
         .fetch_optional(&state.pool)
         .await
         .map_err(db_error)?
         .ok_or_else(|| (StatusCode::NOT_FOUND, "Slot not found").into_response())?;
 
+    let lbp1_play_count = slot.lbp1_play_count.unwrap_or_default();
+    let lbp2_play_count = slot.lbp2_play_count.unwrap_or_default();
+    let lbp3_play_count = slot.lbp3_play_count.unwrap_or_default();
+    let lbp1_completion_count = slot.lbp1_completion_count.unwrap_or_default();
+    let lbp2_completion_count = slot.lbp2_completion_count.unwrap_or_default();
+    let lbp3_completion_count = slot.lbp3_completion_count.unwrap_or_default();
+
+    if let Some(story_id) = slot.story_id {
+        return Ok(Xml(developer_slot(story_id, DeveloperSlotCounts {
+            heart_count: slot.heart_count.unwrap_or_default(),
+            thumbsup: slot.thumbsup.unwrap_or_default(),
+            thumbsdown: slot.thumbsdown.unwrap_or_default(),
+            average_rating: slot.average_rating.unwrap_or_default(),
+            your_lbp1_rating: slot.your_lbp1_rating.unwrap_or_default(),
+            your_rating: slot.your_rating.unwrap_or_default(),
+            your_lbp1_play_count: slot.your_lbp1_play_count.unwrap_or_default(),
+            your_lbp2_play_count: slot.your_lbp2_play_count.unwrap_or_default(),
+            comment_count: slot.comment_count.unwrap_or_default(),
+            photo_count: slot.photo_count.unwrap_or_default(),
+            lbp1_play_count,
+            lbp1_completion_count,
+            lbp1_unique_play_count: slot.lbp1_unique_play_count.unwrap_or_default(),
+            lbp2_play_count,
+            lbp2_completion_count,
+            lbp2_unique_play_count: slot.lbp2_unique_play_count.unwrap_or_default(),
+            lbp3_play_count,
+            lbp3_completion_count,
+            lbp3_unique_play_count: slot.lbp3_unique_play_count.unwrap_or_default(),
+        })));
+    }
+
     Ok(Xml(xml!(
         slot type="user" {
             id { (slot.id) }
-            npHandle { (slot.author_oid) }
+            npHandle { (slot.author_oid.unwrap_or_default()) }
             location {
                 x { (slot.location_x) }
                 y { (slot.location_y) }
@@ -43,7 +106,7 @@ async fn slot(
             game { (slot.gamever) }
             name { (slot.name) }
             description { (slot.description) }
-            rootLevel { (slot.root_level) }
+            rootLevel { (slot.root_level.unwrap_or_default()) }
             icon { (slot.icon) }
             initiallyLocked { (slot.initially_locked) }
             isSubLevel { (slot.is_sub_level) }
@@ -52,36 +115,37 @@ async fn slot(
             minPlayers { (slot.min_players) }
             maxPlayers { (slot.max_players) }
             heartCount { (slot.heart_count.unwrap_or_default()) }
-            thumbsup { "0" }
-            thumbsdown { "0" }
-            averageRating { "0.0" } // lbp1
+            thumbsup { (slot.thumbsup.unwrap_or_default()) }
+            thumbsdown { (slot.thumbsdown.unwrap_or_default()) }
+            averageRating { (slot.average_rating.unwrap_or_default()) } // lbp1
             playerCount { "0" }
             matchingPlayers { "0" }
             mmpick { (slot.mmpicked_at.is_some()) }
-            yourRating { "0" } // lbp1
-            yourDPadRating { "0" } // lbp2+
-            yourlbp1PlayCount { "0" }
-            yourlbp2PlayCount { "0" }
-            reviewCount { "0" }
+            yourRating { (slot.your_lbp1_rating.unwrap_or_default()) } // lbp1
+            yourDPadRating { (slot.your_rating.unwrap_or_default()) } // lbp2+
+            yourlbp1PlayCount { (slot.your_lbp1_play_count.unwrap_or_default()) }
+            yourlbp2PlayCount { (slot.your_lbp2_play_count.unwrap_or_default()) }
+            reviewCount { (slot.review_count.unwrap_or_default()) }
             commentCount { (slot.comment_count.unwrap_or_default()) }
-            photoCount { "0" }
-            authorPhotoCount { "0" }
-            labels {}
+            photoCount { (slot.photo_count.unwrap_or_default()) }
+            authorPhotoCount { (slot.author_photo_count.unwrap_or_default()) }
+            tags { (slot.top_tags.join(",")) } // lbp1
+            labels { (slot.labels.join(",")) }
             firstPublished { (slot.published_at.timestamp_millis()) }
             lastUpdated { (slot.updated_at.timestamp_millis()) }
             commentsEnabled { "true" }
             reviewsEnabled { "true" }
-            playCount { "0" } // all games
-            completionCount { "0" } // all games
-            lbp1PlayCount { "0" }
-            lbp1CompletionCount { "0" }
-            lbp1UniquePlayCount { "0" }
-            lbp2PlayCount { "0" }
-            lbp2CompletionCount { "0" }
-            uniquePlayCount { "0" }
-            lbp3PlayCount { "0" }
-            lbp3CompletionCount { "0" }
-            lbp3UniquePlayCount { "0" }
+            playCount { (&(lbp1_play_count + lbp2_play_count + lbp3_play_count)) } // all games
+            completionCount { (&(lbp1_completion_count + lbp2_completion_count + lbp3_completion_count)) } // all games
+            lbp1PlayCount { (lbp1_play_count) }
+            lbp1CompletionCount { (lbp1_completion_count) }
+            lbp1UniquePlayCount { (slot.lbp1_unique_play_count.unwrap_or_default()) }
+            lbp2PlayCount { (lbp2_play_count) }
+            lbp2CompletionCount { (lbp2_completion_count) }
+            uniquePlayCount { (slot.lbp2_unique_play_count.unwrap_or_default()) }
+            lbp3PlayCount { (lbp3_play_count) }
+            lbp3CompletionCount { (lbp3_completion_count) }
+            lbp3UniquePlayCount { (slot.lbp3_unique_play_count.unwrap_or_default()) }
         }
     )))
 }
\ No newline at end of file
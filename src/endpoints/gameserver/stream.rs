@@ -0,0 +1,316 @@
+use axum::{Router, routing::get, extract::{Path, State}, response::{IntoResponse, Response}, Extension};
+use axum_extra::extract::Query;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use http::StatusCode;
+use maud::html as xml;
+use serde::Deserialize;
+use sqlx::QueryBuilder;
+
+use crate::{extractors::Xml, types::SessionData, utils::db::{check_slot_visibility, db_error}, AppState};
+
+use super::{find_slot_id, SlotType};
+
+// the game only shows a handful of groups, so there's no point in sending more than this
+const MAX_EVENTS: i64 = 500;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/stream", get(stream))
+        .route("/stream/slot/:slot_type/:id", get(slot_stream))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamQuery {
+    // unix timestamps in milliseconds, the game pages backwards from the newest events
+    timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+}
+
+enum StreamFilter {
+    // the user's own activity, and activity of/on hearted users and levels
+    Following,
+    // None for developer slots without a row, which can't have any activity yet
+    Slot(Option<i64>),
+}
+
+#[derive(sqlx::FromRow)]
+struct Event {
+    event_type: String,
+    timestamp: NaiveDateTime,
+    actor: String,
+    actor_icon: Option<String>,
+    target_user: Option<String>,
+    target_user_icon: Option<String>,
+    slot_id: Option<i64>,
+    story_id: Option<i64>,
+    slot_name: Option<String>,
+    slot_icon: Option<String>,
+    slot_author: Option<String>,
+    comment_id: Option<i64>,
+    photo_id: Option<i64>,
+    #[sqlx(default)]
+    count: i64,
+}
+
+impl Event {
+    // developer slots are referred to by their story id
+    fn slot_ref(&self) -> Option<(&'static str, i64)> {
+        match (self.slot_id, self.story_id) {
+            (Some(_), Some(story_id)) => Some(("developer", story_id)),
+            (Some(slot_id), None) => Some(("user", slot_id)),
+            (None, _) => None,
+        }
+    }
+}
+
+struct LevelGroup {
+    slot: (&'static str, i64),
+    timestamp: NaiveDateTime,
+    events: Vec<Event>,
+}
+
+struct UserGroup {
+    actor: String,
+    timestamp: NaiveDateTime,
+    events: Vec<Event>,
+    levels: Vec<LevelGroup>,
+}
+
+async fn stream(
+    query: Query<StreamQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    render_stream(StreamFilter::Following, query, session, state).await
+}
+
+async fn slot_stream(
+    Path((slot_type, id)): Path<(SlotType, i64)>,
+    query: Query<StreamQuery>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let slot_id = find_slot_id(slot_type, id, &state).await?;
+    if let Some(slot_id) = slot_id {
+        check_slot_visibility(slot_id, &session, &state).await?;
+    }
+    render_stream(StreamFilter::Slot(slot_id), query, session, state).await
+}
+
+async fn render_stream(
+    filter: StreamFilter,
+    params: Query<StreamQuery>,
+    session: Extension<SessionData>,
+    state: AppState,
+) -> Result<impl IntoResponse, Response> {
+    let newest = match params.timestamp {
+        Some(ts) => DateTime::from_timestamp_millis(ts)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid timestamp").into_response())?
+            .naive_utc(),
+        None => Utc::now().naive_utc(),
+    };
+    let oldest = match params.end_timestamp {
+        Some(ts) => DateTime::from_timestamp_millis(ts)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid timestamp").into_response())?
+            .naive_utc(),
+        None => newest - Duration::weeks(1),
+    };
+
+    let mut sql = QueryBuilder::new(
+        "SELECT activity.event_type, activity.timestamp, activity.slot_id, activity.comment_id, activity.photo_id,
+        actor.online_id AS actor, actor.icon AS actor_icon,
+        target.online_id AS target_user, target.icon AS target_user_icon,
+        slots.story_id, slots.name AS slot_name, slots.icon AS slot_icon,
+        slot_author.online_id AS slot_author
+        FROM activity
+        JOIN users actor ON activity.actor = actor.id
+        LEFT JOIN users target ON activity.target_user = target.id
+        LEFT JOIN slots ON activity.slot_id = slots.id
+        LEFT JOIN users slot_author ON slots.author = slot_author.id"
+    );
+    sql.push(" WHERE activity.timestamp <= ");
+    sql.push_bind(newest);
+    sql.push(" AND activity.timestamp > ");
+    sql.push_bind(oldest);
+
+    // hidden profiles and levels stay hidden, and levels have to be playable in the user's game
+    sql.push(" AND (actor.profile_visibility != 'myself' OR actor.id = ");
+    sql.push_bind(session.user_id);
+    sql.push(") AND (target.id IS NULL OR target.profile_visibility != 'myself' OR target.id = ");
+    sql.push_bind(session.user_id);
+    sql.push(") AND (slot_author.id IS NULL OR slot_author.level_visibility != 'myself' OR slot_author.id = ");
+    sql.push_bind(session.user_id);
+    sql.push(") AND (slots.id IS NULL OR (slots.gamever <= ");
+    sql.push_bind(session.game_version as i16);
+    sql.push(" AND ((slots.is_sub_level = FALSE AND slots.hidden = FALSE) OR slots.author = ");
+    sql.push_bind(session.user_id);
+    sql.push(")))");
+
+    match filter {
+        StreamFilter::Following => {
+            sql.push(" AND (activity.actor = ");
+            sql.push_bind(session.user_id);
+            sql.push(" OR activity.target_user = ");
+            sql.push_bind(session.user_id);
+            sql.push(" OR slots.author = ");
+            sql.push_bind(session.user_id);
+            sql.push(" OR activity.actor IN (SELECT target_id FROM favourite_users WHERE user_id = ");
+            sql.push_bind(session.user_id);
+            sql.push(") OR activity.target_user IN (SELECT target_id FROM favourite_users WHERE user_id = ");
+            sql.push_bind(session.user_id);
+            sql.push(") OR activity.slot_id IN (SELECT slot_id FROM favourite_slots WHERE user_id = ");
+            sql.push_bind(session.user_id);
+            sql.push("))");
+        },
+        StreamFilter::Slot(slot_id) => {
+            sql.push(" AND activity.slot_id = ");
+            sql.push_bind(slot_id);
+        },
+    }
+
+    sql.push(" ORDER BY activity.timestamp DESC LIMIT ");
+    sql.push_bind(MAX_EVENTS);
+
+    let events = sql.build_query_as::<Event>()
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    // slots and users referenced by the events, the game looks these up by id
+    let mut slots: Vec<&Event> = Vec::new();
+    let mut users: Vec<(&str, Option<&str>)> = Vec::new();
+    for event in &events {
+        if event.slot_ref().is_some() && !slots.iter().any(|s| s.slot_id == event.slot_id) {
+            slots.push(event);
+        }
+        for user in [
+            Some((event.actor.as_str(), event.actor_icon.as_deref())),
+            event.target_user.as_deref().map(|u| (u, event.target_user_icon.as_deref())),
+        ].into_iter().flatten() {
+            if !users.iter().any(|(online_id, _)| *online_id == user.0) {
+                users.push(user);
+            }
+        }
+    }
+    let slots = xml!(
+        @for slot in slots {
+            @let (slot_type, id) = slot.slot_ref().unwrap();
+            slot type=(slot_type) {
+                id { (id) }
+                @if slot_type == "user" {
+                    npHandle { (slot.slot_author.as_deref().unwrap_or_default()) }
+                    name { (slot.slot_name.as_deref().unwrap_or_default()) }
+                    icon { (slot.slot_icon.as_deref().unwrap_or_default()) }
+                }
+            }
+        }
+    );
+    let users = xml!(
+        @for (online_id, icon) in users {
+            user type="user" {
+                npHandle icon=(icon.unwrap_or_default()) { (online_id) }
+            }
+        }
+    );
+
+    // events are grouped per day and user, then per level, newest first.
+    // a group's timestamp is its newest event, so its date is the day it covers
+    let mut groups: Vec<UserGroup> = Vec::new();
+    for mut event in events {
+        event.count = 1;
+        let group = match groups.iter().position(|g| g.actor == event.actor && g.timestamp.date() == event.timestamp.date()) {
+            Some(i) => &mut groups[i],
+            None => {
+                groups.push(UserGroup {
+                    actor: event.actor.clone(),
+                    timestamp: event.timestamp,
+                    events: Vec::new(),
+                    levels: Vec::new(),
+                });
+                groups.last_mut().unwrap()
+            },
+        };
+
+        let Some(slot) = event.slot_ref() else {
+            group.events.push(event);
+            continue;
+        };
+        let level = match group.levels.iter().position(|l| l.slot == slot) {
+            Some(i) => &mut group.levels[i],
+            None => {
+                group.levels.push(LevelGroup {
+                    slot,
+                    timestamp: event.timestamp,
+                    events: Vec::new(),
+                });
+                group.levels.last_mut().unwrap()
+            },
+        };
+
+        // repeated plays of the same level show up as one event
+        match level.events.iter_mut().find(|e| e.event_type == "play_level" && event.event_type == "play_level") {
+            Some(play) => play.count += 1,
+            None => level.events.push(event),
+        }
+    }
+
+    Ok(Xml(xml!(
+        stream {
+            start_timestamp { (newest.timestamp_millis()) }
+            end_timestamp { (oldest.timestamp_millis()) }
+            groups {
+                @for group in &groups {
+                    group type="user" {
+                        timestamp { (group.timestamp.timestamp_millis()) }
+                        user_id { (group.actor) }
+                        events {
+                            @for event in &group.events {
+                                (render_event(event))
+                            }
+                        }
+                        subgroups {
+                            @for level in &group.levels {
+                                group type="level" {
+                                    timestamp { (level.timestamp.timestamp_millis()) }
+                                    slot_id type=(level.slot.0) { (level.slot.1) }
+                                    events {
+                                        @for event in &level.events {
+                                            (render_event(event))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            slots { (slots) }
+            users { (users) }
+        }
+    )))
+}
+
+fn render_event(event: &Event) -> maud::Markup {
+    xml!(
+        event type=(event.event_type) {
+            timestamp { (event.timestamp.timestamp_millis()) }
+            actor { (event.actor) }
+            @if let Some((slot_type, id)) = event.slot_ref() {
+                object_slot_id type=(slot_type) { (id) }
+            }
+            @if let Some(target_user) = &event.target_user {
+                object_user { (target_user) }
+            }
+            @if let Some(comment_id) = event.comment_id {
+                comment_id { (comment_id) }
+            }
+            @if let Some(photo_id) = event.photo_id {
+                photo_id { (photo_id) }
+            }
+            @if event.event_type == "play_level" {
+                count { (event.count) }
+            }
+        }
+    )
+}
\ No newline at end of file
@@ -1,19 +1,23 @@
-use axum::{Router, routing::get};
+use std::str::FromStr;
+
+use axum::{Router, routing::{get, post}, extract::{Path, State}, http::StatusCode, response::{IntoResponse, Response}, Extension, Form};
+use serde::Deserialize;
 use strum::IntoEnumIterator;
-use strum_macros::{EnumIter, IntoStaticStr};
+use strum_macros::{EnumIter, EnumString, IntoStaticStr};
 
-use crate::AppState;
+use crate::{AppState, types::{GameVersion, SessionData}, utils::db::{check_slot, check_slot_visibility, db_error}};
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/tags", get(tags))
+        .route("/tag/user/:id", post(tag_slot))
 }
 
 // tags for levels
 // LBP1 only
 #[allow(non_camel_case_types)]
-#[derive(EnumIter, IntoStaticStr)]
-enum Tags {
+#[derive(EnumIter, EnumString, IntoStaticStr)]
+pub enum Tags {
     Brilliant,
     Beautiful,
     Funky,
@@ -99,3 +103,54 @@ async fn tags() -> String {
     }
     tags.join(",")
 }
+
+impl Tags {
+    pub fn parse(tag: &str) -> Option<Self> {
+        Self::from_str(tag.strip_prefix("TAG_")?).ok()
+    }
+}
+
+#[derive(Deserialize)]
+struct TagPayload {
+    t: String,
+}
+
+// each player gets one tag per slot, tagging again replaces it
+async fn tag_slot(
+    Path(slot_id): Path<i64>,
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Form<TagPayload>,
+) -> Result<impl IntoResponse, Response> {
+    if !matches!(session.game_version, GameVersion::Lbp1) {
+        return Err((StatusCode::BAD_REQUEST, "Tags are only used in LBP1").into_response());
+    }
+    if Tags::parse(&payload.t).is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Invalid tag").into_response());
+    }
+    check_slot(slot_id, &state).await?;
+    check_slot_visibility(slot_id, &session, &state).await?;
+
+    let author = sqlx::query!("SELECT author FROM slots WHERE id = $1", slot_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?
+        .author;
+    if author == Some(session.user_id) {
+        return Err((StatusCode::BAD_REQUEST, "Cannot tag your own slot").into_response());
+    }
+
+    sqlx::query!(
+        "INSERT INTO slot_tags (user_id, slot_id, tag) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, slot_id) DO UPDATE
+        SET tag = EXCLUDED.tag, timestamp = CURRENT_TIMESTAMP",
+        session.user_id,
+        slot_id,
+        payload.t,
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(StatusCode::OK)
+}
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use axum::{
     routing::{get, post},
     Router,
@@ -13,8 +15,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     extractors::Xml,
-    types::{GameVersion, SessionData, ResourceRef},
-    utils::{resource::get_hash_path, serde::double_option_err, db::db_error},
+    types::{GameVersion, SessionData, ResourceRef, Visibility},
+    utils::{resource::get_hash_path, serde::double_option_err, db::{db_error, get_id_from_username, check_user_visibility}},
     AppState,
     extractors::Json,
 };
@@ -25,11 +27,12 @@ pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/user/:online_id", get(user))
         .route("/users", get(users))
+        .route("/favouriteUsers/:online_id", get(favourite_users))
         .route("/updateUser", post(update_user))
         .route("/get_my_pins", get(get_my_pins))
         .route("/update_my_pins", post(update_my_pins))
         .route("/privacySettings", get(privacy_settings))
-        .route("/privacySettings", post(privacy_settings))
+        .route("/privacySettings", post(update_privacy_settings))
 }
 
 async fn user(
@@ -44,7 +47,14 @@ async fn user(
         COUNT(DISTINCT lbp1slot.id) AS lbp1slot_count,
         COUNT(DISTINCT lbp2slot.id) AS lbp2slot_count,
         COUNT(DISTINCT lbp3slot.id) AS lbp3slot_count,
-        COUNT(DISTINCT favourite_slots.slot_id) AS favourite_slot_count
+        COUNT(DISTINCT favourite_slots.slot_id) AS favourite_slot_count,
+        (SELECT COUNT(*) FROM photos WHERE author = users.id) AS photos_by_count,
+        (SELECT COUNT(*) FROM photo_subjects WHERE user_id = users.id) AS photos_with_count,
+        (SELECT COUNT(*) FROM favourite_users WHERE target_id = users.id) AS heart_count,
+        (SELECT COUNT(*) FROM favourite_users WHERE user_id = users.id) AS favourite_user_count,
+        (SELECT COUNT(*) FROM playlists WHERE author = users.id) AS list_count,
+        (SELECT COUNT(*) FROM reviews WHERE author = users.id) AS review_count,
+        EXISTS(SELECT timestamp FROM favourite_users WHERE user_id = $2 AND target_id = users.id) AS your_heart
         FROM users
         LEFT JOIN comments ON users.id = comments.target_user
         LEFT JOIN slots lbp1slot ON users.id = lbp1slot.author AND lbp1slot.gamever = 0
@@ -53,13 +63,18 @@ async fn user(
         LEFT JOIN favourite_slots ON users.id = favourite_slots.user_id
         WHERE online_id = $1
         GROUP BY users.id",
-        online_id
+        online_id,
+        session.user_id,
     )
         .fetch_optional(&state.pool)
         .await
         .map_err(db_error)?
         .ok_or_else(|| (StatusCode::NOT_FOUND, "User not found").into_response())?;
 
+    if !Visibility::from_str(&user.profile_visibility).unwrap().is_visible_to(user.id, &session) {
+        return Err((StatusCode::NOT_FOUND, "User not found").into_response());
+    }
+
     let slot_limit = state.config.slot_limit as i64;
     let lbp1slot_count = user.lbp1slot_count.unwrap_or_default();
     let lbp2slot_count = user.lbp2slot_count.unwrap_or_default();
@@ -84,9 +99,10 @@ async fn user(
             lbp3EntitledSlots { (slot_limit) }
             lbp3PurchasedSlots { "0" }
             lbp3FreeSlots { (&(slot_limit - lbp3slot_count)) }
-            lists { "0" }
-            lists_quota { "20" }
-            heartCount { "0" }
+            lists { (user.list_count.unwrap_or_default()) }
+            lists_quota { (state.config.list_limit) }
+            heartCount { (user.heart_count.unwrap_or_default()) }
+            yourHeart { (user.your_heart.unwrap_or_default()) }
             planets {(
                 match session.game_version {
                     GameVersion::Lbp1 => "",
@@ -98,17 +114,17 @@ async fn user(
             yay2 { (user.yay2.as_deref().unwrap_or_default()) }
             boo2 { (user.boo2.as_deref().unwrap_or_default()) }
             biography { (user.biography) }
-            reviewCount { "0" }
+            reviewCount { (user.review_count.unwrap_or_default()) }
             commentCount { (user.comment_count.unwrap_or_default()) }
-            photosByMeCount { "0" }
-            photosWithMeCount { "0" }
+            photosByMeCount { (user.photos_by_count.unwrap_or_default()) }
+            photosWithMeCount { (user.photos_with_count.unwrap_or_default()) }
             commentsEnabled { "true" }
             location {
                 x { (user.location_x) }
                 y { (user.location_y) }
             }
             favouriteSlotCount { (user.favourite_slot_count.unwrap_or_default()) }
-            favouriteUserCount { "0" }
+            favouriteUserCount { (user.favourite_user_count.unwrap_or_default()) }
             lolcatftwCount { "0" } // this is the queue, why the fuck would you do this mm
             pins {
                 // https://stackoverflow.com/a/61052611
@@ -118,7 +134,7 @@ async fn user(
             staffChallengeGoldCount { "0" }
             staffChallengeSilverCount { "0" }
             staffChallengeBronzeCount { "0" }
-            photos {} // TODO: make separate photo entity?
+            photos {}
             /*clientsConnected {
                 lbp1 { "true" }
                 lbp2 { "true" }
@@ -137,10 +153,13 @@ struct UsersQuery {
 async fn users(
     query: Query<UsersQuery>,
     State(state): State<AppState>,
+    session: Extension<SessionData>,
 ) -> Result<impl IntoResponse, Response> {
     let mut users = sqlx::query!(
-        "SELECT online_id, icon FROM users WHERE online_id = ANY($1)",
-        &query.u
+        "SELECT online_id, icon FROM users
+        WHERE online_id = ANY($1) AND (profile_visibility != 'myself' OR id = $2)",
+        &query.u,
+        session.user_id,
     )
     .fetch(&state.pool);
 
@@ -155,6 +174,55 @@ async fn users(
     )))
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FavouriteUsersQuery {
+    page_start: i64,
+    page_size: i64,
+}
+
+async fn favourite_users(
+    query: Query<FavouriteUsersQuery>,
+    State(state): State<AppState>,
+    Path(online_id): Path<String>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let hearted_by = get_id_from_username(&online_id, &state).await?;
+    check_user_visibility(hearted_by, &session, &state).await?;
+
+    let users = sqlx::query!(
+        "SELECT users.online_id, users.icon,
+        (SELECT COUNT(*) FROM favourite_users WHERE target_id = users.id) AS heart_count,
+        COUNT(*) OVER() AS total
+        FROM favourite_users hearts
+        JOIN users ON hearts.target_id = users.id
+        WHERE hearts.user_id = $1 AND (users.profile_visibility != 'myself' OR users.id = $4)
+        ORDER BY hearts.timestamp DESC
+        LIMIT $2 OFFSET $3",
+        hearted_by,
+        query.page_size,
+        query.page_start - 1,
+        session.user_id,
+    )
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    let total = users.first().map_or(0, |u| u.total.unwrap_or_default());
+    let hint_start = users.len() + 1;
+
+    Ok(Xml(xml!(
+        favouriteUsers total=(total) hint_start=(hint_start) {
+            @for user in users {
+                user type="user" {
+                    npHandle icon=(user.icon.as_deref().unwrap_or_default()) { (user.online_id) }
+                    heartCount { (user.heart_count.unwrap_or_default()) }
+                }
+            }
+        }
+    )))
+}
+
 #[derive(Deserialize, Debug)]
 struct SlotList {
     #[serde(default)]
@@ -229,7 +297,7 @@ async fn update_user(
         .map_err(db_error)?;
     }
     if let Some(bio) = &payload.biography {
-        sqlx::query!("UPDATE users SET biography = $1 WHERE id = $2", bio, uid)
+        sqlx::query!("UPDATE users SET biography = $1 WHERE id = $2", state.text_filter.censor(bio), uid)
             .execute(&state.pool)
             .await
             .map_err(db_error)?;
@@ -398,11 +466,61 @@ async fn update_my_pins(
     Ok(payload)
 }
 
-async fn privacy_settings() -> impl IntoResponse {
-    Xml(xml!(
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrivacySettingsPayload {
+    level_visibility: Option<Visibility>,
+    profile_visibility: Option<Visibility>,
+}
+
+async fn privacy_settings(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+) -> Result<impl IntoResponse, Response> {
+    let settings = sqlx::query!(
+        "SELECT level_visibility, profile_visibility FROM users WHERE id = $1",
+        session.user_id
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?
+    .ok_or_else(|| (StatusCode::NOT_FOUND, "User not found").into_response())?;
+
+    Ok(Xml(xml!(
         privacySettings {
-            levelVisibility { "all" }
-            profileVisibility { "all" }
+            levelVisibility { (settings.level_visibility) }
+            profileVisibility { (settings.profile_visibility) }
         }
-    ))
+    )))
+}
+
+async fn update_privacy_settings(
+    State(state): State<AppState>,
+    session: Extension<SessionData>,
+    payload: Xml<PrivacySettingsPayload>,
+) -> Result<impl IntoResponse, Response> {
+    if let Some(visibility) = payload.level_visibility {
+        let visibility: &str = visibility.into();
+        sqlx::query!(
+            "UPDATE users SET level_visibility = $1 WHERE id = $2",
+            visibility,
+            session.user_id
+        )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    }
+    if let Some(visibility) = payload.profile_visibility {
+        let visibility: &str = visibility.into();
+        sqlx::query!(
+            "UPDATE users SET profile_visibility = $1 WHERE id = $2",
+            visibility,
+            session.user_id
+        )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    }
+
+    privacy_settings(State(state), session).await
 }
\ No newline at end of file
@@ -0,0 +1,84 @@
+use std::str::FromStr;
+
+use axum::{
+    Router,
+    routing::get,
+    extract::{Path, State},
+    http::{StatusCode, HeaderValue},
+    response::{IntoResponse, Response},
+    body::Body,
+};
+use tracing::warn;
+
+use crate::{types::ResourceRef, utils::{db::db_error, texture::get_png}, AppState};
+
+// icons as pngs for web viewers, there's no session here so only public levels and profiles are served
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/icon/slot/:id", get(slot_icon))
+        .route("/icon/user/:online_id", get(user_icon))
+}
+
+async fn slot_icon(
+    Path(slot_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    let icon = sqlx::query!(
+        "SELECT slots.icon FROM slots JOIN users ON slots.author = users.id
+        WHERE slots.id = $1 AND slots.hidden = FALSE AND users.level_visibility = 'all'",
+        slot_id,
+    )
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Slot not found").into_response())?
+        .icon;
+
+    serve_icon(&icon, state).await
+}
+
+async fn user_icon(
+    Path(online_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, Response> {
+    let icon = sqlx::query!(
+        "SELECT icon FROM users WHERE online_id = $1 AND profile_visibility = 'all'",
+        online_id,
+    )
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "User not found").into_response())?
+        .icon
+        .unwrap_or_default();
+
+    serve_icon(&icon, state).await
+}
+
+async fn serve_icon(icon: &str, state: AppState) -> Result<Response, Response> {
+    // guid icons are part of the game, so there's nothing to convert
+    let hash = match ResourceRef::from_str(icon) {
+        Ok(ResourceRef::Hash(hash)) => hash,
+        _ => return Err((StatusCode::NOT_FOUND, "Icon isn't an uploaded resource").into_response()),
+    };
+
+    // decoding textures is too slow to do on the async runtime
+    let path = tokio::task::spawn_blocking(move || {
+        get_png(&state.config.resource_dir, &state.config.image_cache_dir, hash)
+    })
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+        .map_err(|e| {
+            warn!("Couldn't convert icon {}: {e:#}", hex::encode(hash));
+            (StatusCode::INTERNAL_SERVER_ERROR, "Couldn't convert icon").into_response()
+        })?;
+
+    let png = tokio::fs::read(path).await.map_err(|e| {
+        (StatusCode::INTERNAL_SERVER_ERROR, format!("Couldn't read converted icon: {e}")).into_response()
+    })?;
+
+    let mut resp = Response::new(Body::from(png));
+    resp.headers_mut().insert("Content-Type", HeaderValue::from_static(mime::IMAGE_PNG.as_ref()));
+
+    Ok(resp)
+}
\ No newline at end of file
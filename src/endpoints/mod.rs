@@ -1,4 +1,6 @@
+pub mod admin;
 mod autodiscover;
 pub mod gameserver;
+pub mod icon;
 
 pub use autodiscover::autodiscover;
\ No newline at end of file
@@ -12,6 +12,7 @@ use tower_sessions_redis_store::{fred::prelude::*, RedisStore};
 use tracing::{warn, info};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use types::Config;
+use utils::text_filter::TextFilter;
 
 mod endpoints;
 mod middleware;
@@ -23,6 +24,7 @@ mod utils;
 struct AppState {
     config: Config,
     pool: Pool<Postgres>,
+    text_filter: TextFilter,
 }
 
 #[tokio::main]
@@ -48,9 +50,12 @@ async fn main() -> Result<(), anyhow::Error> {
         .await
         .context("can't connect to database")?;
 
+    let text_filter = TextFilter::from_config(&config.text_filter)?;
+
     let state = AppState {
         config: config.clone(),
         pool,
+        text_filter,
     };
 
     types::pub_key_store::init_keys();
@@ -70,10 +75,17 @@ async fn main() -> Result<(), anyhow::Error> {
                 .with_expiry(Expiry::OnInactivity(time::Duration::minutes(30)))
         );
 
-    let app = Router::new()
+    let mut app = Router::new()
         .nest(&config.base_path, endpoints::gameserver::routes(&config).await)
         .layer(session_service)
         .route("/autodiscover", get(endpoints::autodiscover))
+        .merge(endpoints::icon::routes());
+
+    if !config.admin_token.is_empty() {
+        app = app.nest("/admin", endpoints::admin::routes(&config));
+    }
+
+    let app = app
         .with_state(state)
         .layer(TraceLayer::new_for_http());
 
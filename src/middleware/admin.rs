@@ -0,0 +1,18 @@
+use axum::{extract::{Request, State}, middleware::Next, response::{Response, IntoResponse}, http::StatusCode};
+
+// the admin api has no game session, so operators authenticate with the token from the config instead
+pub async fn verify_admin_token(
+    State(admin_token): State<String>,
+    req: Request,
+    next: Next,
+) -> Result<impl IntoResponse, Response> {
+    let token = req.headers().get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "));
+
+    if token != Some(admin_token.as_str()) {
+        return Err(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    Ok(next.run(req).await)
+}
\ No newline at end of file
@@ -1,5 +1,7 @@
+mod admin;
 mod digest;
 mod session;
 
+pub use admin::verify_admin_token;
 pub use digest::{verify_digest, send_digest};
 pub use session::{remove_set_cookie, parse_session};
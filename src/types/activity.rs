@@ -0,0 +1,48 @@
+use strum_macros::IntoStaticStr;
+use uuid::Uuid;
+
+// things users do that show up in the game's recent activity
+// the variant names double as the event types in the stream
+#[derive(Debug, Clone, Copy, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum Activity {
+    PublishLevel(i64),
+    HeartLevel(i64),
+    PlayLevel(i64),
+    CommentOnLevel { slot_id: i64, comment_id: i64 },
+    HeartUser(Uuid),
+    CommentOnUser { user_id: Uuid, comment_id: i64 },
+    UploadPhoto { photo_id: i64, slot_id: Option<i64> },
+}
+
+impl Activity {
+    pub fn slot_id(&self) -> Option<i64> {
+        match *self {
+            Self::PublishLevel(slot_id) | Self::HeartLevel(slot_id) | Self::PlayLevel(slot_id)
+                | Self::CommentOnLevel { slot_id, .. } => Some(slot_id),
+            Self::UploadPhoto { slot_id, .. } => slot_id,
+            Self::HeartUser(_) | Self::CommentOnUser { .. } => None,
+        }
+    }
+
+    pub fn target_user(&self) -> Option<Uuid> {
+        match *self {
+            Self::HeartUser(user_id) | Self::CommentOnUser { user_id, .. } => Some(user_id),
+            _ => None,
+        }
+    }
+
+    pub fn comment_id(&self) -> Option<i64> {
+        match *self {
+            Self::CommentOnLevel { comment_id, .. } | Self::CommentOnUser { comment_id, .. } => Some(comment_id),
+            _ => None,
+        }
+    }
+
+    pub fn photo_id(&self) -> Option<i64> {
+        match *self {
+            Self::UploadPhoto { photo_id, .. } => Some(photo_id),
+            _ => None,
+        }
+    }
+}
\ No newline at end of file
@@ -13,6 +13,7 @@ pub struct Config {
     pub db_conn: String,
     pub redis_conn: String,
     pub log_level: String,
+    pub admin_token: String,
 
     pub server_desc: String,
     pub banner_image_url: Option<Url>,
@@ -22,7 +23,12 @@ pub struct Config {
 
     pub resource_dir: String,
     pub resource_size_limit: u32,
+    pub image_cache_dir: String,
     pub slot_limit: u32,
+    pub list_limit: u32,
+    pub grief_report_threshold: u32,
+
+    pub text_filter: TextFilterConfig,
 
     pub create_user_on_connect: bool,
     pub rename_users_automatically: bool,
@@ -32,6 +38,13 @@ pub struct Config {
     pub verify_npticket_expiry: bool,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct TextFilterConfig {
+    pub words: Vec<String>,
+    pub wordlists: Vec<String>,
+    pub patterns: Vec<String>,
+}
+
 impl Config {
     pub fn parse_from_file(path: &str) -> Self {
         let file = File::open(path).expect("Couldn't open config file");
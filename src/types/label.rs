@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use strum_macros::EnumString;
+
+// level labels, LBP2 onwards
+// the game sends them prefixed with "LABEL_"
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, EnumString)]
+pub enum Label {
+    SinglePlayer,
+    Multiplayer,
+    Quick,
+    Long,
+    Challenging,
+    Easy,
+    Scary,
+    Funny,
+    Artistic,
+    Musical,
+    Intricate,
+    Cinematic,
+    Competitive,
+    Fighter,
+    Gallery,
+    Puzzle,
+    Platform,
+    Race,
+    Shooter,
+    Sports,
+    Story,
+    Strategy,
+    SurvivalChallenge,
+    Tutorial,
+    Retro,
+    Collectables,
+    DirectControl,
+    Explore,
+    FirstPerson,
+    #[strum(serialize = "3rdPerson")]
+    ThirdPerson,
+    Sci_Fi,
+    Social,
+    Arcade,
+    Board_Game,
+    CardGame,
+    Mini_Game,
+    Party_Game,
+    Defence,
+    Driving,
+    Hangout,
+    Hide_And_Seek,
+    Prop_Hunt,
+    Music_Gallery,
+    Costume_Gallery,
+    Sticker_Gallery,
+    Movie,
+    Pinball,
+    Technology,
+    Homage,
+    #[strum(serialize = "8_Bit")]
+    Eight_Bit,
+    #[strum(serialize = "16_Bit")]
+    Sixteen_Bit,
+    Seasonal,
+    Time_Trial,
+    Paint,
+    Water,
+    Grapple,
+    PowerGlove,
+    Sackbots,
+    // LBP3
+    INTERACTIVE_STREAM,
+    QUESTS,
+    SACKBOY,
+    SPRINGINATOR,
+    HOVERBOARD_NAME,
+    FLOATY_FLUID_NAME,
+    ODDSOCK,
+    TOGGLE,
+    SWOOP,
+    CREATED_CHARACTERS,
+    HEROCAPE,
+    MEMORISER,
+    WALLJUMP,
+    ATTRACT_GEL,
+    ATTRACT_TWEAK,
+    SACKPOCKET,
+    TOP_DOWN,
+}
+
+impl Label {
+    pub fn parse(label: &str) -> Option<Self> {
+        Self::from_str(label.strip_prefix("LABEL_")?).ok()
+    }
+}
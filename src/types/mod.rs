@@ -1,14 +1,22 @@
+mod activity;
 mod config;
 mod game_version;
+mod label;
 mod npticket;
 mod platform;
 pub mod pub_key_store;
+mod resource;
 mod resource_ref;
 mod session_data;
+mod visibility;
 
-pub use config::Config;
+pub use activity::Activity;
+pub use config::{Config, TextFilterConfig};
 pub use game_version::GameVersion;
+pub use label::Label;
 pub use npticket::NpTicket;
 pub use platform::Platform;
+pub use resource::{ResourceInfo, ResourceType};
 pub use resource_ref::ResourceRef;
 pub use session_data::SessionData;
+pub use visibility::Visibility;
@@ -1,131 +1,194 @@
-use std::{str::FromStr, fmt, io::{Cursor, Read}};
+use std::{fmt, io::{self, Cursor, Read, Seek, SeekFrom, Take}};
 
-use actix_web::web::Bytes;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use byteorder::{ReadBytesExt, BigEndian};
-use serde::{de, Deserialize, Deserializer};
 
-use crate::utils::resource::{get_hash_path, str_to_hash};
+use crate::utils::compression::ChunkedDecoder;
 
-#[derive(Debug, Clone)]
-pub enum ResourceRef {
-    Guid(u32),
-    Hash([u8; 20]),
-}
+use super::{GameVersion, ResourceRef};
 
-impl ResourceRef {
-    pub fn exists(&self, resource_dir: &str) -> bool {
-        match self {
-            Self::Guid(_) => true,
-            Self::Hash(h) => get_hash_path(resource_dir, *h).exists()
-        }
-    }
-}
-
-impl FromStr for ResourceRef {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some(guid) = s.strip_prefix('g') {
-            Ok(Self::Guid(
-                guid.parse::<u32>().context("Couldn't parse guid")?,
-            ))
-        } else {
-            Ok(Self::Hash(
-                str_to_hash(s)?
-            ))
-        }
-    }
-}
-
-impl<'de> Deserialize<'de> for ResourceRef {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer<'de>
-    {
-        let s = String::deserialize(deserializer)?;
-        FromStr::from_str(&s).map_err(de::Error::custom)
-    }
-}
-
-impl fmt::Display for ResourceRef {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Guid(g) => write!(f, "g{g}"),
-            Self::Hash(h) => write!(f, "{}", hex::encode(h)),
-        }
-    }
-}
+// size of the CellGcmTexture struct GTF textures have in front of their data
+const GTF_HEADER_SIZE: u64 = 0x18;
 
 #[derive(Debug)]
 pub struct ResourceInfo {
-    res_type: ResourceType,
-    revision: Option<u32>,
-    // TODO: implement dependency table parsing
-    dependency_table: Option<u32>,
-    branch: Option<ResourceBranch>,
-    compression_flags: Option<u8>,
-    is_compressed: Option<bool>,
+    pub res_type: ResourceType,
+    pub revision: Option<u32>,
+    pub dependencies: Vec<Dependency>,
+    pub branch: Option<ResourceBranch>,
+    pub compression_flags: Option<u8>,
+    pub is_compressed: Option<bool>,
+    body_offset: u64,
+    body_end: u64,
 }
 
 impl ResourceInfo {
-    pub fn parse_from_res(data: Bytes) -> Self {
-        let mut rdr = Cursor::new(data);
-        
-        let res_type = ResourceType::from_magic(&mut rdr);
+    pub fn parse_from_res(data: &[u8]) -> Result<Self> {
+        Self::parse(&mut Cursor::new(data))
+    }
+
+    // only the header and dependency table get read, so this works on files too
+    pub fn parse<R: Read + Seek>(rdr: &mut R) -> Result<Self> {
+        let len = rdr.seek(SeekFrom::End(0))?;
+        rdr.rewind()?;
+
+        let res_type = ResourceType::from_magic(rdr)?;
 
         let mut revision = None;
-        let mut dependency_table = None;
+        let mut dependencies = Vec::new();
         let mut branch = None;
         let mut compression_flags = None;
         let mut is_compressed = None;
+        let mut body_offset = 4;
+        let mut body_end = len;
 
         match res_type {
             ResourceType::Texture => {},
-            ResourceType::GtfTexture => {},
-            ResourceType::Jpeg => {},
-            ResourceType::Png => {},
-            ResourceType::Unknown => {},
+            ResourceType::GtfTexture => body_offset += GTF_HEADER_SIZE,
+            ResourceType::Jpeg => body_offset = 0,
+            ResourceType::Png => body_offset = 0,
             _ => {
-                let rev = rdr.read_u32::<BigEndian>().unwrap();
+                let rev = rdr.read_u32::<BigEndian>().context("Missing revision")?;
                 revision = Some(rev);
 
                 if rev >= 0x109 {
-                    dependency_table = Some(rdr.read_u32::<BigEndian>().unwrap());
+                    let offset = rdr.read_u32::<BigEndian>().context("Missing dependency table offset")?;
                     if rev >= 0x189 {
                         match res_type {
                             ResourceType::Mesh => {},
                             _ => {
                                 if rev >= 0x271 {
                                     branch = Some(ResourceBranch {
-                                        id: rdr.read_u16::<BigEndian>().unwrap(),
-                                        revision: rdr.read_u16::<BigEndian>().unwrap(),
+                                        id: rdr.read_u16::<BigEndian>().context("Missing branch id")?,
+                                        revision: rdr.read_u16::<BigEndian>().context("Missing branch revision")?,
                                     });
                                 }
-                                let branch = branch.as_ref().unwrap();
-                                if rev >= 0x297
-                                || /* leerdammer */ (rev == 0x272 && branch.id == 0x4c44 && branch.revision >= 0x2) {
-                                    compression_flags = Some(rdr.read_u8().unwrap());
+                                let is_leerdammer = branch.as_ref()
+                                    .is_some_and(|b| rev == 0x272 && b.id == 0x4c44 && b.revision >= 0x2);
+                                if rev >= 0x297 || is_leerdammer {
+                                    compression_flags = Some(rdr.read_u8().context("Missing compression flags")?);
                                 }
-                                is_compressed = Some(rdr.read_u8().unwrap() != 0);
+                                is_compressed = Some(rdr.read_u8().context("Missing compression flag")? != 0);
                             }
                         }
                     }
+
+                    // the table comes after the data, so it can't point into the header or past the end
+                    body_offset = rdr.stream_position()?;
+                    if (offset as u64) < body_offset || offset as u64 > len {
+                        bail!("Dependency table offset is out of bounds");
+                    }
+                    body_end = offset as u64;
+                    rdr.seek(SeekFrom::Start(body_end))?;
+                    dependencies = Dependency::parse_table(rdr)?;
+                } else {
+                    body_offset = rdr.stream_position()?;
                 }
             }
         };
+        if body_offset > len {
+            bail!("Resource is too short");
+        }
 
-        Self {
+        Ok(Self {
             res_type,
             revision,
-            dependency_table,
+            dependencies,
             branch,
             compression_flags,
             is_compressed,
+            body_offset,
+            body_end,
+        })
+    }
+
+    // textures are always compressed, and binary resources are unless their header says otherwise
+    pub fn is_body_compressed(&self) -> bool {
+        match self.res_type {
+            ResourceType::Texture | ResourceType::GtfTexture => true,
+            ResourceType::Jpeg | ResourceType::Png => false,
+            _ => self.is_compressed.unwrap_or(true),
         }
     }
+
+    // streams the resource's data with the compression undone, `rdr` has to be the same resource this was parsed from
+    pub fn body<R: Read + Seek>(&self, mut rdr: R) -> Result<ResourceBody<R>> {
+        rdr.seek(SeekFrom::Start(self.body_offset))?;
+        let data = rdr.take(self.body_end - self.body_offset);
+        Ok(match self.is_body_compressed() {
+            true => ResourceBody::Compressed(ChunkedDecoder::new(data).context("Couldn't read compression header")?),
+            false => ResourceBody::Raw(data),
+        })
+    }
 }
 
-#[derive(Debug)]
+pub enum ResourceBody<R: Read> {
+    Raw(Take<R>),
+    Compressed(ChunkedDecoder<Take<R>>),
+}
+
+impl<R: Read> Read for ResourceBody<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Raw(rdr) => rdr.read(buf),
+            Self::Compressed(rdr) => rdr.read(buf),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub resource: ResourceRef,
+    // the table stores the numeric type the game uses internally, which
+    // covers a lot more than what can be uploaded
+    pub type_id: u32,
+    pub res_type: Option<ResourceType>,
+}
+
+impl Dependency {
+    const FLAG_HASH: u8 = 1;
+    const FLAG_GUID: u8 = 2;
+
+    fn parse_table<R: Read>(rdr: &mut R) -> Result<Vec<Self>> {
+        let count = rdr.read_u32::<BigEndian>().context("Missing dependency count")?;
+
+        let mut dependencies = Vec::new();
+        for _ in 0..count {
+            let flags = rdr.read_u8().context("Missing dependency flags")?;
+
+            let guid = match flags & Self::FLAG_GUID != 0 {
+                true => Some(rdr.read_u32::<BigEndian>().context("Missing dependency guid")?),
+                false => None,
+            };
+            let hash = match flags & Self::FLAG_HASH != 0 {
+                true => {
+                    let mut hash = [0u8; 20];
+                    rdr.read_exact(&mut hash).context("Missing dependency hash")?;
+                    Some(hash)
+                },
+                false => None,
+            };
+
+            // when both are present the hash is what actually gets loaded
+            let resource = match (hash, guid) {
+                (Some(hash), _) => ResourceRef::Hash(hash),
+                (None, Some(guid)) => ResourceRef::Guid(guid),
+                (None, None) => bail!("Dependency has neither a guid nor a hash"),
+            };
+
+            let type_id = rdr.read_u32::<BigEndian>().context("Missing dependency type")?;
+            dependencies.push(Self {
+                resource,
+                type_id,
+                res_type: ResourceType::from_type_id(type_id),
+            });
+        }
+
+        Ok(dependencies)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ResourceType {
     Texture,            // TEX
     GtfTexture,         // GTF
@@ -144,14 +207,13 @@ pub enum ResourceType {
     StreamingChunk,     // CHK
     Jpeg,
     Png,
-    Unknown,
 }
 
 impl ResourceType {
-    pub fn from_magic(rdr: &mut Cursor<Bytes>) -> Self {
+    pub fn from_magic<R: Read + Seek>(rdr: &mut R) -> Result<Self> {
         let mut magic = [0u8; 4];
-        rdr.read_exact(&mut magic).unwrap();
-        match &magic {
+        rdr.read_exact(&mut magic).context("Resource is too short")?;
+        Ok(match &magic {
             b"TEX " => Self::Texture,
             b"GTF " => Self::GtfTexture,
             b"LVLb" => Self::Level,
@@ -167,24 +229,67 @@ impl ResourceType {
             b"ADCb" => Self::AdventureCreate,
             b"ADSb" => Self::AdventureShared,
             b"CHKb" => Self::StreamingChunk,
-            [0xFF, 0xD8, 0xFF, 0xE0] => Self::Jpeg,
+            [0xFF, 0xD8, 0xFF, _] => Self::Jpeg,
             _ => {
-                rdr.set_position(0);
+                rdr.rewind()?;
                 let mut magic = [0u8; 8];
-                rdr.read_exact(&mut magic).unwrap();
-                if magic == [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A] {
-                    return Self::Png
+                if rdr.read_exact(&mut magic).is_ok() && magic == [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A] {
+                    return Ok(Self::Png)
                 }
-                Self::Unknown
+                bail!("Unknown resource magic")
             }
+        })
+    }
+
+    // type ids used in dependency tables, GTF textures are referenced as regular textures.
+    // ids without a matching magic here (e.g. 60 for vita cross-play data, 63 for adventure
+    // play profiles) are left unknown
+    pub fn from_type_id(type_id: u32) -> Option<Self> {
+        match type_id {
+            1 => Some(Self::Texture),
+            2 => Some(Self::Mesh),
+            7 => Some(Self::GfxMaterial),
+            9 => Some(Self::Level),
+            11 => Some(Self::FishScript),
+            38 => Some(Self::Plan),
+            46 => Some(Self::Voice),
+            52 => Some(Self::MoveRecording),
+            53 => Some(Self::Painting),
+            54 => Some(Self::Quest),
+            61 => Some(Self::StreamingChunk),
+            62 => Some(Self::AdventureShared),
+            _ => None,
+        }
+    }
+
+    // the first game that can upload this type, None if it should never come from a client
+    fn introduced_in(&self) -> Option<GameVersion> {
+        match self {
+            Self::Texture | Self::GtfTexture | Self::Level | Self::Plan
+                | Self::Voice | Self::Jpeg | Self::Png => Some(GameVersion::Lbp1),
+            Self::Painting | Self::CrossLevel | Self::MoveRecording => Some(GameVersion::Lbp2),
+            Self::Quest | Self::AdventureCreate | Self::AdventureShared
+                | Self::StreamingChunk => Some(GameVersion::Lbp3),
+            // scripts, meshes and materials only ever ship with the game
+            Self::FishScript | Self::Mesh | Self::GfxMaterial => None,
         }
     }
+
+    pub fn is_allowed_in(&self, game_version: GameVersion) -> bool {
+        self.introduced_in().is_some_and(|v| v as u8 <= game_version as u8)
+    }
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
 }
 
 #[derive(Debug)]
 pub struct ResourceBranch {
-    id: u16,
-    revision: u16,
+    pub id: u16,
+    pub revision: u16,
 }
 
 /*#[derive(Debug)]
@@ -193,4 +298,72 @@ pub enum SerializationType {
     CompressedTexture,  // (space)
     GtfSwizzled,        // s
     GxtSwizzled,        // S
-}*/
\ No newline at end of file
+}*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: [u8; 20] = [0xAB; 20];
+
+    fn table(count: u32, entries: &[&[u8]]) -> Cursor<Vec<u8>> {
+        let mut data = count.to_be_bytes().to_vec();
+        for entry in entries {
+            data.extend_from_slice(entry);
+        }
+        Cursor::new(data)
+    }
+
+    fn entry(flags: u8, guid: Option<u32>, hash: Option<[u8; 20]>, type_id: u32) -> Vec<u8> {
+        let mut data = vec![flags];
+        if let Some(guid) = guid {
+            data.extend_from_slice(&guid.to_be_bytes());
+        }
+        if let Some(hash) = hash {
+            data.extend_from_slice(&hash);
+        }
+        data.extend_from_slice(&type_id.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn hash_dependency() {
+        let deps = Dependency::parse_table(&mut table(1, &[&entry(1, None, Some(HASH), 1)])).unwrap();
+        assert_eq!(deps.len(), 1);
+        assert!(matches!(deps[0].resource, ResourceRef::Hash(h) if h == HASH));
+        assert_eq!(deps[0].type_id, 1);
+        assert_eq!(deps[0].res_type, Some(ResourceType::Texture));
+    }
+
+    #[test]
+    fn guid_dependency() {
+        let deps = Dependency::parse_table(&mut table(1, &[&entry(2, Some(10682), None, 38)])).unwrap();
+        assert!(matches!(deps[0].resource, ResourceRef::Guid(10682)));
+        assert_eq!(deps[0].res_type, Some(ResourceType::Plan));
+    }
+
+    #[test]
+    fn hash_and_guid_dependency_uses_hash() {
+        let deps = Dependency::parse_table(&mut table(2, &[
+            &entry(3, Some(10682), Some(HASH), 63),
+            &entry(2, Some(123), None, 9),
+        ])).unwrap();
+        assert_eq!(deps.len(), 2);
+        assert!(matches!(deps[0].resource, ResourceRef::Hash(h) if h == HASH));
+        assert_eq!(deps[0].res_type, None);
+        assert!(matches!(deps[1].resource, ResourceRef::Guid(123)));
+        assert_eq!(deps[1].res_type, Some(ResourceType::Level));
+    }
+
+    #[test]
+    fn dependency_without_reference() {
+        assert!(Dependency::parse_table(&mut table(1, &[&entry(0, None, None, 1)])).is_err());
+    }
+
+    #[test]
+    fn truncated_table() {
+        assert!(Dependency::parse_table(&mut table(2, &[&entry(1, None, Some(HASH), 1)])).is_err());
+        assert!(Dependency::parse_table(&mut table(1, &[&entry(1, None, Some(HASH), 1)[..10]])).is_err());
+        assert!(Dependency::parse_table(&mut Cursor::new(vec![0, 0])).is_err());
+    }
+}
\ No newline at end of file
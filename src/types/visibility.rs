@@ -0,0 +1,26 @@
+use serde::Deserialize;
+use strum_macros::{EnumString, IntoStaticStr};
+use uuid::Uuid;
+
+use super::SessionData;
+
+// privacy options the game offers for levels and profiles
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, EnumString, IntoStaticStr)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Visibility {
+    All,
+    // only users signed in through psn/rpcn. everyone on the gameserver is, so in game this
+    // works the same as All. the web routes don't have a session and only serve All
+    Psn,
+    Myself,
+}
+
+impl Visibility {
+    pub fn is_visible_to(&self, owner: Uuid, session: &SessionData) -> bool {
+        match self {
+            Self::All | Self::Psn => true,
+            Self::Myself => owner == session.user_id,
+        }
+    }
+}
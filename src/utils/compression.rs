@@ -0,0 +1,153 @@
+use std::io::{self, Read};
+
+use byteorder::{ReadBytesExt, BigEndian};
+use flate2::read::ZlibDecoder;
+
+// compressed resources are split into zlib streams, preceded by a table of their
+// compressed and decompressed sizes. the sizes are u16s, so a chunk is always
+// under 64 KiB and only one chunk is held in memory at a time
+pub struct ChunkedDecoder<R: Read> {
+    inner: R,
+    chunks: Vec<(u16, u16)>,
+    next_chunk: usize,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> ChunkedDecoder<R> {
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        // always 1, possibly a version
+        inner.read_u16::<BigEndian>()?;
+        let count = inner.read_u16::<BigEndian>()?;
+
+        let mut chunks = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let compressed = inner.read_u16::<BigEndian>()?;
+            let decompressed = inner.read_u16::<BigEndian>()?;
+            chunks.push((compressed, decompressed));
+        }
+
+        Ok(Self {
+            inner,
+            chunks,
+            next_chunk: 0,
+            buf: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    fn fill_buf(&mut self) -> io::Result<()> {
+        let (compressed, decompressed) = self.chunks[self.next_chunk];
+        self.next_chunk += 1;
+        self.buf.clear();
+        self.pos = 0;
+
+        let mut chunk = (&mut self.inner).take(compressed as u64);
+        // chunks that wouldn't get any smaller are stored as-is
+        if compressed == decompressed {
+            chunk.read_to_end(&mut self.buf)?;
+        } else {
+            let mut decoder = ZlibDecoder::new(chunk);
+            // one byte more than expected is enough to tell the size is wrong, without inflating a zip bomb
+            (&mut decoder).take(decompressed as u64 + 1).read_to_end(&mut self.buf)?;
+            // the next chunk has to start right after this one, even if zlib didn't need all of it
+            io::copy(&mut decoder.into_inner(), &mut io::sink())?;
+        }
+
+        if self.buf.len() != decompressed as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Chunk has the wrong decompressed size"));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ChunkedDecoder<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.next_chunk == self.chunks.len() {
+                return Ok(0);
+            }
+            self.fill_buf()?;
+        }
+
+        let len = out.len().min(self.buf.len() - self.pos);
+        out[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    // chunks are (data as stored, decompressed size)
+    fn compressed(chunks: &[(&[u8], u16)]) -> Vec<u8> {
+        let mut data = vec![0, 1];
+        data.extend_from_slice(&(chunks.len() as u16).to_be_bytes());
+        for (chunk, decompressed) in chunks {
+            data.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+            data.extend_from_slice(&decompressed.to_be_bytes());
+        }
+        for (chunk, _) in chunks {
+            data.extend_from_slice(chunk);
+        }
+        data
+    }
+
+    fn decode(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        ChunkedDecoder::new(data)?.read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn stored_chunks() {
+        let data = compressed(&[(b"hello ", 6), (b"world", 5)]);
+        assert_eq!(decode(&data).unwrap(), b"hello world");
+    }
+
+    #[test]
+    fn deflated_chunks() {
+        let first = [b'a'; 1000];
+        let second = [b'b'; 500];
+        let data = compressed(&[(&deflate(&first), 1000), (b"stored", 6), (&deflate(&second), 500)]);
+
+        let mut expected = first.to_vec();
+        expected.extend_from_slice(b"stored");
+        expected.extend_from_slice(&second);
+        assert_eq!(decode(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn wrong_decompressed_size() {
+        let chunk = [b'a'; 1000];
+        // both too much and too little data get rejected
+        assert!(decode(&compressed(&[(&deflate(&chunk), 100)])).is_err());
+        assert!(decode(&compressed(&[(&deflate(&chunk), 2000)])).is_err());
+    }
+
+    #[test]
+    fn truncated_table() {
+        let data = compressed(&[(b"hello", 5), (b"world", 5)]);
+        // cut off in the middle of the second size pair
+        assert!(ChunkedDecoder::new(&data[..10]).is_err());
+        assert!(ChunkedDecoder::new(&data[..3]).is_err());
+    }
+
+    #[test]
+    fn truncated_chunk() {
+        let data = compressed(&[(b"hello", 5), (b"world", 5)]);
+        assert!(decode(&data[..data.len() - 2]).is_err());
+    }
+}
\ No newline at end of file
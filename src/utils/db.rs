@@ -1,8 +1,11 @@
+use std::str::FromStr;
+
 use axum::response::{IntoResponse, Response};
 use http::StatusCode;
+use sqlx::PgExecutor;
 use uuid::Uuid;
 
-use crate::AppState;
+use crate::{types::{Activity, GameVersion, SessionData, Visibility}, AppState};
 
 pub fn db_error(error: sqlx::Error) -> Response {
     (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response()
@@ -23,7 +26,7 @@ pub async fn check_slot(
     slot_id: i64,
     state: &AppState,
 ) -> Result<(), Response> {
-    let slot_exists = sqlx::query!("SELECT EXISTS(SELECT id FROM slots WHERE id = $1)", slot_id)
+    let slot_exists = sqlx::query!("SELECT EXISTS(SELECT id FROM slots WHERE id = $1 AND story_id IS NULL)", slot_id)
         .fetch_one(&state.pool)
         .await
         .map_err(db_error)?
@@ -43,7 +46,7 @@ pub async fn check_slot_author(
     state: &AppState,
 ) -> Result<(), Response> {
     let is_author = sqlx::query!(
-        "SELECT author = $2 AS is_author FROM slots WHERE id = $1",
+        "SELECT author = $2 AS is_author FROM slots WHERE id = $1 AND story_id IS NULL",
         slot_id,
         user_id,
     )
@@ -61,6 +64,108 @@ pub async fn check_slot_author(
     Ok(())
 }
 
+pub async fn check_moderator(
+    session: &SessionData,
+    state: &AppState,
+) -> Result<(), Response> {
+    let is_moderator = sqlx::query!("SELECT is_moderator FROM users WHERE id = $1", session.user_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .is_some_and(|u| u.is_moderator);
+
+    if !is_moderator {
+        return Err((StatusCode::FORBIDDEN, "Only moderators can do this").into_response())
+    }
+
+    Ok(())
+}
+
+// hidden profiles are treated as if they don't exist
+pub async fn check_user_visibility(
+    user_id: Uuid,
+    session: &SessionData,
+    state: &AppState,
+) -> Result<(), Response> {
+    let visibility = sqlx::query!("SELECT profile_visibility FROM users WHERE id = $1", user_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "User not found").into_response())?
+        .profile_visibility;
+
+    if !Visibility::from_str(&visibility).unwrap().is_visible_to(user_id, session) {
+        return Err((StatusCode::NOT_FOUND, "User not found").into_response())
+    }
+
+    Ok(())
+}
+
+pub async fn check_slot_visibility(
+    slot_id: i64,
+    session: &SessionData,
+    state: &AppState,
+) -> Result<(), Response> {
+    let slot = sqlx::query!(
+        "SELECT slots.author, slots.hidden, users.level_visibility AS \"level_visibility?\",
+        (SELECT is_moderator FROM users WHERE id = $2) AS \"is_moderator!\"
+        FROM slots LEFT JOIN users ON slots.author = users.id
+        WHERE slots.id = $1",
+        slot_id,
+        session.user_id,
+    )
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Slot not found").into_response())?;
+
+    // hidden slots are still visible to their author and to moderators looking into reports
+    if slot.hidden && slot.author != Some(session.user_id) && !slot.is_moderator {
+        return Err((StatusCode::NOT_FOUND, "Slot not found").into_response())
+    }
+
+    // developer slots don't have an author
+    if let (Some(author), Some(visibility)) = (slot.author, slot.level_visibility) {
+        if !Visibility::from_str(&visibility).unwrap().is_visible_to(author, session) {
+            return Err((StatusCode::NOT_FOUND, "Slot not found").into_response())
+        }
+    }
+
+    Ok(())
+}
+
+// developer slots don't have a row until someone interacts with them, so it gets created here.
+// only for actions that store something, reads go through find_developer_slot_id
+pub async fn get_developer_slot_id(
+    story_id: i64,
+    game_version: GameVersion,
+    state: &AppState,
+) -> Result<i64, Response> {
+    Ok(
+        sqlx::query!(
+            "INSERT INTO slots (story_id, name, gamever) VALUES ($1, '', $2)
+            ON CONFLICT (story_id) DO UPDATE SET story_id = EXCLUDED.story_id
+            RETURNING id",
+            story_id,
+            game_version as i16,
+        )
+            .fetch_one(&state.pool)
+            .await
+            .map_err(db_error)?
+            .id
+    )
+}
+
+pub async fn find_developer_slot_id(story_id: i64, state: &AppState) -> Result<Option<i64>, Response> {
+    Ok(
+        sqlx::query!("SELECT id FROM slots WHERE story_id = $1", story_id)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)?
+            .map(|s| s.id)
+    )
+}
+
 pub async fn is_slot_hearted(
     user_id: Uuid,
     slot_id: i64,
@@ -101,4 +206,276 @@ pub async fn is_slot_queued(
             .exists
             .unwrap()
     )
+}
+
+pub async fn is_user_hearted(
+    user_id: Uuid,
+    target_id: Uuid,
+    state: &AppState,
+) -> Result<bool, Response> {
+    Ok(
+        sqlx::query!(
+            "SELECT EXISTS(
+                SELECT timestamp FROM favourite_users
+                WHERE user_id = $1 AND target_id = $2
+            )",
+            user_id, target_id
+        )
+            .fetch_one(&state.pool)
+            .await
+            .map_err(db_error)?
+            .exists
+            .unwrap()
+    )
+}
+
+pub async fn is_playlist_hearted(
+    user_id: Uuid,
+    playlist_id: i64,
+    state: &AppState,
+) -> Result<bool, Response> {
+    Ok(
+        sqlx::query!(
+            "SELECT EXISTS(
+                SELECT timestamp FROM favourite_playlists
+                WHERE user_id = $1 AND playlist_id = $2
+            )",
+            user_id, playlist_id
+        )
+            .fetch_one(&state.pool)
+            .await
+            .map_err(db_error)?
+            .exists
+            .unwrap()
+    )
+}
+
+pub async fn check_playlist(
+    playlist_id: i64,
+    state: &AppState,
+) -> Result<(), Response> {
+    let playlist_exists = sqlx::query!("SELECT EXISTS(SELECT id FROM playlists WHERE id = $1)", playlist_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?
+        .exists
+        .unwrap();
+
+    if !playlist_exists {
+        return Err((StatusCode::NOT_FOUND, "Playlist not found").into_response())
+    }
+
+    Ok(())
+}
+
+// playlists of hidden profiles are treated as if they don't exist too
+pub async fn check_playlist_visibility(
+    playlist_id: i64,
+    session: &SessionData,
+    state: &AppState,
+) -> Result<(), Response> {
+    let playlist = sqlx::query!(
+        "SELECT playlists.author, users.profile_visibility
+        FROM playlists JOIN users ON playlists.author = users.id
+        WHERE playlists.id = $1",
+        playlist_id,
+    )
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Playlist not found").into_response())?;
+
+    if !Visibility::from_str(&playlist.profile_visibility).unwrap().is_visible_to(playlist.author, session) {
+        return Err((StatusCode::NOT_FOUND, "Playlist not found").into_response())
+    }
+
+    Ok(())
+}
+
+pub async fn check_playlist_author(
+    playlist_id: i64,
+    user_id: Uuid,
+    state: &AppState,
+) -> Result<(), Response> {
+    let is_author = sqlx::query!(
+        "SELECT author = $2 AS is_author FROM playlists WHERE id = $1",
+        playlist_id,
+        user_id,
+    )
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Playlist not found").into_response())?
+        .is_author
+        .unwrap();
+
+    if !is_author {
+        return Err((StatusCode::UNAUTHORIZED, "Cannot modify another user's playlist").into_response())
+    }
+
+    Ok(())
+}
+
+// queues a notification, shown to the user the next time the game asks for them
+pub async fn notify(user_id: Uuid, content: &str, state: &AppState) -> Result<(), Response> {
+    sqlx::query!("INSERT INTO notifications (user_id, content) VALUES ($1, $2)", user_id, content)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(())
+}
+
+// logs an event for the recent activity stream
+pub async fn record_activity(user_id: Uuid, activity: Activity, state: &AppState) -> Result<(), Response> {
+    let event_type: &'static str = activity.into();
+    sqlx::query!(
+        "INSERT INTO activity (actor, event_type, slot_id, target_user, comment_id, photo_id)
+        VALUES ($1, $2, $3, $4, $5, $6)",
+        user_id,
+        event_type,
+        activity.slot_id(),
+        activity.target_user(),
+        activity.comment_id(),
+        activity.photo_id(),
+    )
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(())
+}
+
+// slots a moderator hid themselves are left alone, only the ones hidden here get unhidden again later
+pub async fn auto_hide_slot(conn: impl PgExecutor<'_>, slot_id: i64, threshold: u32) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE slots SET hidden = TRUE, auto_hidden = TRUE
+        WHERE id = $1 AND story_id IS NULL AND hidden = FALSE
+        AND (SELECT COUNT(DISTINCT reporter) FROM grief_reports WHERE slot_id = $1 AND status = 'open') >= $2",
+        slot_id,
+        threshold as i64,
+    )
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+// once reports get dismissed or resolved without acting on the slot, the ones left open might not be enough anymore.
+// a threshold of 0 turns auto hiding off, so anything it hid before comes back
+pub async fn auto_unhide_slot(conn: impl PgExecutor<'_>, slot_id: i64, threshold: u32) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE slots SET hidden = FALSE, auto_hidden = FALSE
+        WHERE id = $1 AND auto_hidden = TRUE
+        AND ($2 = 0 OR (SELECT COUNT(DISTINCT reporter) FROM grief_reports WHERE slot_id = $1 AND status = 'open') < $2)",
+        slot_id,
+        threshold as i64,
+    )
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+// fixtures for #[sqlx::test], which runs every test in its own freshly migrated database
+#[cfg(test)]
+pub mod testing {
+    use sqlx::PgExecutor;
+    use uuid::Uuid;
+
+    pub async fn create_user(conn: impl PgExecutor<'_>) -> Uuid {
+        let id = Uuid::new_v4();
+        let online_id = &id.simple().to_string()[..16];
+        sqlx::query!("INSERT INTO users (id, online_id) VALUES ($1, $2)", id, online_id)
+            .execute(conn)
+            .await
+            .unwrap();
+        id
+    }
+
+    pub async fn create_slot(conn: impl PgExecutor<'_>, author: Uuid) -> i64 {
+        sqlx::query!(
+            "INSERT INTO slots (name, author, root_level, gamever) VALUES ('test', $1, '', 1) RETURNING id",
+            author,
+        )
+            .fetch_one(conn)
+            .await
+            .unwrap()
+            .id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::{auto_hide_slot, auto_unhide_slot, testing::{create_slot, create_user}};
+
+    async fn report(pool: &PgPool, reporter: Uuid, slot_id: i64) -> i64 {
+        sqlx::query!(
+            "INSERT INTO grief_reports (reporter, grief_type, slot_id, level_type) VALUES ($1, 1, $2, 'user') RETURNING id",
+            reporter,
+            slot_id,
+        )
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .id
+    }
+
+    async fn dismiss(pool: &PgPool, id: i64) {
+        sqlx::query!("UPDATE grief_reports SET status = 'dismissed' WHERE id = $1", id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn is_hidden(pool: &PgPool, slot_id: i64) -> bool {
+        sqlx::query!("SELECT hidden FROM slots WHERE id = $1", slot_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+            .hidden
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn dismissing_a_report_unhides_an_auto_hidden_slot(pool: PgPool) {
+        let author = create_user(&pool).await;
+        let slot_id = create_slot(&pool, author).await;
+
+        let reporter = create_user(&pool).await;
+        let first = report(&pool, reporter, slot_id).await;
+        auto_hide_slot(&pool, slot_id, 2).await.unwrap();
+        assert!(!is_hidden(&pool, slot_id).await);
+
+        let reporter = create_user(&pool).await;
+        report(&pool, reporter, slot_id).await;
+        auto_hide_slot(&pool, slot_id, 2).await.unwrap();
+        assert!(is_hidden(&pool, slot_id).await);
+
+        dismiss(&pool, first).await;
+        auto_unhide_slot(&pool, slot_id, 2).await.unwrap();
+        assert!(!is_hidden(&pool, slot_id).await);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn slots_hidden_by_a_moderator_stay_hidden(pool: PgPool) {
+        let author = create_user(&pool).await;
+        let slot_id = create_slot(&pool, author).await;
+        sqlx::query!("UPDATE slots SET hidden = TRUE WHERE id = $1", slot_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let reporter = create_user(&pool).await;
+        let first = report(&pool, reporter, slot_id).await;
+        let reporter = create_user(&pool).await;
+        report(&pool, reporter, slot_id).await;
+        auto_hide_slot(&pool, slot_id, 2).await.unwrap();
+
+        dismiss(&pool, first).await;
+        auto_unhide_slot(&pool, slot_id, 2).await.unwrap();
+        assert!(is_hidden(&pool, slot_id).await);
+    }
 }
\ No newline at end of file
@@ -3,3 +3,6 @@ pub mod ticket_read;
 pub mod serde;
 pub mod predicate;
 pub mod db;
+pub mod text_filter;
+pub mod compression;
+pub mod texture;
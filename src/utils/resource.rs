@@ -1,6 +1,9 @@
-use std::path::PathBuf;
+use std::{collections::{HashSet, VecDeque}, fs::File, io::BufReader, path::PathBuf};
 
 use anyhow::{Result, Context};
+use tracing::debug;
+
+use crate::types::{ResourceInfo, ResourceRef};
 
 pub fn str_to_hash(str: &str) -> Result<[u8; 20]> {
     hex::decode(str)
@@ -14,3 +17,49 @@ pub fn get_hash_path(resource_dir: &str, hash: [u8; 20]) -> PathBuf {
     path.push(hex::encode(hash));
     path
 }
+
+#[derive(Debug, Default)]
+pub struct DependencyWalk {
+    // uploaded resources reachable from the root, not including the root itself
+    pub dependencies: Vec<[u8; 20]>,
+    pub missing: Vec<[u8; 20]>,
+}
+
+// follows hash dependencies through every uploaded resource, guids are
+// skipped since they refer to resources that ship with the game
+pub fn walk_dependencies(resource_dir: &str, root: [u8; 20]) -> Result<DependencyWalk> {
+    let mut walk = DependencyWalk::default();
+    if !get_hash_path(resource_dir, root).exists() {
+        walk.missing.push(root);
+        return Ok(walk);
+    }
+
+    let mut seen = HashSet::from([root]);
+    let mut queue = VecDeque::from([root]);
+    while let Some(hash) = queue.pop_front() {
+        let file = File::open(get_hash_path(resource_dir, hash))
+            .with_context(|| format!("Couldn't read resource {}", hex::encode(hash)))?;
+        let info = ResourceInfo::parse(&mut BufReader::new(file))
+            .with_context(|| format!("Couldn't parse resource {}", hex::encode(hash)))?;
+
+        for dep in info.dependencies {
+            let ResourceRef::Hash(dep_hash) = dep.resource else { continue };
+            if !seen.insert(dep_hash) {
+                continue;
+            }
+
+            if get_hash_path(resource_dir, dep_hash).exists() {
+                walk.dependencies.push(dep_hash);
+                queue.push_back(dep_hash);
+            } else {
+                debug!(
+                    "{} depends on missing resource {} ({:?}, type id {})",
+                    hex::encode(hash), hex::encode(dep_hash), dep.res_type, dep.type_id,
+                );
+                walk.missing.push(dep_hash);
+            }
+        }
+    }
+
+    Ok(walk)
+}
\ No newline at end of file
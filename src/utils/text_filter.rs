@@ -0,0 +1,144 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+use crate::types::TextFilterConfig;
+
+// censors user-written text, matched characters get replaced with asterisks
+#[derive(Debug, Clone)]
+pub struct TextFilter {
+    regex: Option<Regex>,
+}
+
+impl TextFilter {
+    pub fn from_config(config: &TextFilterConfig) -> Result<Self> {
+        let mut words = config.words.clone();
+        for path in &config.wordlists {
+            let wordlist = fs::read_to_string(path)
+                .with_context(|| format!("Couldn't read wordlist {path}"))?;
+            words.extend(wordlist.lines().map(str::trim).filter(|w| !w.is_empty()).map(String::from));
+        }
+
+        // plain words only match whole words, patterns are used as-is
+        let patterns: Vec<String> = words.iter()
+            .map(|w| word_pattern(w))
+            .chain(config.patterns.iter().map(|p| format!("(?:{p})")))
+            .collect();
+
+        if patterns.is_empty() {
+            return Ok(Self { regex: None });
+        }
+
+        let regex = RegexBuilder::new(&patterns.join("|"))
+            .case_insensitive(true)
+            .build()
+            .context("Couldn't build text filter")?;
+
+        Ok(Self { regex: Some(regex) })
+    }
+
+    pub fn censor(&self, text: &str) -> String {
+        match &self.regex {
+            Some(regex) => regex
+                .replace_all(text, |caps: &regex::Captures| "*".repeat(caps[0].chars().count()))
+                .into_owned(),
+            None => text.to_string(),
+        }
+    }
+}
+
+// \b only works next to a word character, so words like "c++" only get it on the side that has one
+fn word_pattern(word: &str) -> String {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let start = match word.chars().next().is_some_and(is_word_char) {
+        true => r"\b",
+        false => "",
+    };
+    let end = match word.chars().last().is_some_and(is_word_char) {
+        true => r"\b",
+        false => "",
+    };
+    format!("{start}{}{end}", regex::escape(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(words: &[&str], patterns: &[&str]) -> TextFilter {
+        TextFilter::from_config(&TextFilterConfig {
+            words: words.iter().map(|w| w.to_string()).collect(),
+            wordlists: Vec::new(),
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        }).unwrap()
+    }
+
+    #[test]
+    fn plain_word() {
+        assert_eq!(filter(&["bad"], &[]).censor("a bad level"), "a *** level");
+    }
+
+    #[test]
+    fn word_with_symbol_edges() {
+        let filter = filter(&["c++", "@admin"], &[]);
+        assert_eq!(filter.censor("i write c++ code"), "i write *** code");
+        assert_eq!(filter.censor("c++, then c++!"), "***, then ***!");
+        assert_eq!(filter.censor("ask @admin now"), "ask ****** now");
+        assert_eq!(filter.censor("ask @administrator"), "ask @administrator");
+    }
+
+    #[test]
+    fn case_insensitive() {
+        assert_eq!(filter(&["bad"], &[]).censor("BAD Bad bAd"), "*** *** ***");
+    }
+
+    #[test]
+    fn substring_is_kept() {
+        let filter = filter(&["ass"], &[]);
+        assert_eq!(filter.censor("class assembly"), "class assembly");
+        assert_eq!(filter.censor("the ass"), "the ***");
+    }
+
+    #[test]
+    fn replacement_keeps_length() {
+        let filter = filter(&["héllo"], &["[0-9]{3}"]);
+        let text = "héllo 12345 world";
+        let censored = filter.censor(text);
+        assert_eq!(censored, "***** ***45 world");
+        assert_eq!(censored.chars().count(), text.chars().count());
+    }
+
+    #[test]
+    fn raw_patterns_are_not_anchored() {
+        assert_eq!(filter(&[], &["b.d"]).censor("abide by bud"), "a***e by ***");
+    }
+
+    #[test]
+    fn wordlist() {
+        let path = std::env::temp_dir().join(format!("sacklite-wordlist-{}", std::process::id()));
+        fs::write(&path, "first\n\n  second  \n").unwrap();
+        let filter = TextFilter::from_config(&TextFilterConfig {
+            words: vec!["third".to_string()],
+            wordlists: vec![path.to_string_lossy().into_owned()],
+            patterns: Vec::new(),
+        });
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(filter.unwrap().censor("first second third fourth"), "***** ****** ***** fourth");
+    }
+
+    #[test]
+    fn missing_wordlist() {
+        assert!(TextFilter::from_config(&TextFilterConfig {
+            words: Vec::new(),
+            wordlists: vec!["/nonexistent/wordlist".to_string()],
+            patterns: Vec::new(),
+        }).is_err());
+    }
+
+    #[test]
+    fn empty_filter() {
+        assert_eq!(filter(&[], &[]).censor("anything goes"), "anything goes");
+    }
+}
\ No newline at end of file
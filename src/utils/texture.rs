@@ -0,0 +1,320 @@
+use std::{fs::{self, File}, io::{BufReader, Cursor, Read, Seek, SeekFrom}, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt, BigEndian, LittleEndian};
+use image::{codecs::dds::DdsDecoder, io::{Limits, Reader as ImageReader}, DynamicImage, ImageFormat, RgbaImage};
+use uuid::Uuid;
+
+use crate::{types::{ResourceInfo, ResourceType}, utils::resource::get_hash_path};
+
+// converted images get scaled down to fit in this
+const THUMBNAIL_SIZE: u32 = 256;
+// dimensions come from uploaded headers, anything bigger is refused before any pixels get allocated
+const MAX_TEXTURE_SIZE: u32 = 4096;
+// magic and DDS_HEADER, the DX10 extension comes after it
+const DDS_HEADER_SIZE: usize = 128;
+const DDS_DX10_HEADER_SIZE: usize = 20;
+
+// CELL_GCM_TEXTURE_* formats, the rest of the format byte are layout flags
+const GTF_FORMAT_MASK: u8 = !0x60;
+const GTF_LINEAR: u8 = 0x20;
+const GTF_A8R8G8B8: u8 = 0x85;
+const GTF_DXT1: u8 = 0x86;
+const GTF_DXT23: u8 = 0x87;
+const GTF_DXT45: u8 = 0x88;
+
+// returns the path of a png version of an image resource, converting it if it isn't cached yet
+pub fn get_png(resource_dir: &str, cache_dir: &str, hash: [u8; 20]) -> Result<PathBuf> {
+    let mut path = PathBuf::from(cache_dir);
+    path.push(format!("{}.png", hex::encode(hash)));
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let file = File::open(get_hash_path(resource_dir, hash)).context("Couldn't open resource")?;
+    let mut rdr = BufReader::new(file);
+    let info = ResourceInfo::parse(&mut rdr)?;
+
+    let image = match info.res_type {
+        ResourceType::Texture => {
+            // the decompressed data is a regular dds file
+            let dds = read_dds(info.body(&mut rdr)?)?;
+            decode_limited(ImageReader::with_format(Cursor::new(dds), ImageFormat::Dds))?
+        },
+        ResourceType::GtfTexture => decode_gtf(&info, &mut rdr)?,
+        ResourceType::Jpeg | ResourceType::Png => {
+            let mut data = Vec::new();
+            info.body(&mut rdr)?.read_to_end(&mut data)?;
+            decode_limited(ImageReader::new(Cursor::new(data)).with_guessed_format()?)?
+        },
+        _ => bail!("{} resources aren't images", info.res_type),
+    };
+
+    let image = match image.width() > THUMBNAIL_SIZE || image.height() > THUMBNAIL_SIZE {
+        true => image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE),
+        false => image,
+    };
+
+    // written under a temporary name first so a half-written file never gets served
+    fs::create_dir_all(cache_dir).context("Couldn't create image cache dir")?;
+    let mut tmp_path = PathBuf::from(cache_dir);
+    tmp_path.push(format!("{}.tmp", Uuid::new_v4()));
+    image.save_with_format(&tmp_path, ImageFormat::Png)?;
+    fs::rename(&tmp_path, &path)?;
+
+    Ok(path)
+}
+
+fn decode_limited(mut reader: ImageReader<Cursor<Vec<u8>>>) -> Result<DynamicImage> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_TEXTURE_SIZE);
+    limits.max_image_height = Some(MAX_TEXTURE_SIZE);
+    reader.limits(limits);
+    Ok(reader.decode()?)
+}
+
+// only reads as much as the header says the top mipmap takes up, since that's all the decoder looks at
+fn read_dds<R: Read>(mut body: R) -> Result<Vec<u8>> {
+    let mut dds = vec![0u8; DDS_HEADER_SIZE];
+    body.read_exact(&mut dds).context("DDS header is too short")?;
+    if &dds[..4] != b"DDS " {
+        bail!("Texture isn't a DDS file");
+    }
+
+    let height = LittleEndian::read_u32(&dds[12..16]);
+    let width = LittleEndian::read_u32(&dds[16..20]);
+    if width == 0 || height == 0 || width > MAX_TEXTURE_SIZE || height > MAX_TEXTURE_SIZE {
+        bail!("DDS texture is {width}x{height}, at most {MAX_TEXTURE_SIZE}x{MAX_TEXTURE_SIZE} is supported");
+    }
+
+    // the decoder only supports dxt formats, which store 4x4 pixel blocks
+    let block_size: u64 = match &dds[84..88] {
+        b"DXT1" => 8,
+        b"DXT3" | b"DXT5" => 16,
+        b"DX10" => {
+            dds.resize(DDS_HEADER_SIZE + DDS_DX10_HEADER_SIZE, 0);
+            body.read_exact(&mut dds[DDS_HEADER_SIZE..]).context("DDS DX10 header is too short")?;
+            // DXGI_FORMAT_BC1_* and DXGI_FORMAT_BC2_*/BC3_*
+            match LittleEndian::read_u32(&dds[DDS_HEADER_SIZE..]) {
+                70..=72 => 8,
+                73..=78 => 16,
+                format => bail!("Unsupported DDS DXGI format {format}"),
+            }
+        },
+        fourcc => bail!("Unsupported DDS format {:?}", String::from_utf8_lossy(fourcc)),
+    };
+
+    let len = width.div_ceil(4) as u64 * height.div_ceil(4) as u64 * block_size;
+    body.take(len).read_to_end(&mut dds)?;
+    Ok(dds)
+}
+
+// gtf textures start with a CellGcmTexture struct describing the data after it
+fn decode_gtf<R: Read + Seek>(info: &ResourceInfo, rdr: &mut R) -> Result<DynamicImage> {
+    rdr.seek(SeekFrom::Start(4))?;
+    let format = rdr.read_u8()?;
+    // mipmap count, dimension, cubemap and component remapping
+    rdr.seek(SeekFrom::Current(7))?;
+    let width = rdr.read_u16::<BigEndian>()? as u32;
+    let height = rdr.read_u16::<BigEndian>()? as u32;
+    if width == 0 || height == 0 || width > MAX_TEXTURE_SIZE || height > MAX_TEXTURE_SIZE {
+        bail!("GTF texture is {width}x{height}, at most {MAX_TEXTURE_SIZE}x{MAX_TEXTURE_SIZE} is supported");
+    }
+
+    let body = info.body(rdr)?;
+    let fourcc = match format & GTF_FORMAT_MASK {
+        GTF_DXT1 => b"DXT1",
+        GTF_DXT23 => b"DXT3",
+        GTF_DXT45 => b"DXT5",
+        // swizzled textures would have to be unswizzled first
+        GTF_A8R8G8B8 if format & GTF_LINEAR != 0 => {
+            let len = (width as u64).checked_mul(height as u64)
+                .and_then(|pixels| pixels.checked_mul(4))
+                .context("Invalid texture size")?;
+            // the buffer only grows as far as the data actually goes, so a lying header can't make it huge
+            let mut argb = Vec::new();
+            body.take(len + 1).read_to_end(&mut argb)?;
+            if argb.len() as u64 != len {
+                bail!("Texture data doesn't match its size");
+            }
+            for pixel in argb.chunks_exact_mut(4) {
+                pixel.rotate_left(1);
+            }
+            let image = RgbaImage::from_raw(width, height, argb).context("Invalid texture size")?;
+            return Ok(DynamicImage::ImageRgba8(image));
+        },
+        _ => bail!("Unsupported GTF texture format {format:#x}"),
+    };
+
+    // dxt blocks are stored the same way as in dds files, so they only need a dds header in front
+    let header = dds_header(width, height, fourcc)?;
+    Ok(DynamicImage::from_decoder(DdsDecoder::new(Cursor::new(header).chain(body))?)?)
+}
+
+fn dds_header(width: u32, height: u32, fourcc: &[u8; 4]) -> Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(128);
+    header.extend_from_slice(b"DDS ");
+    header.write_u32::<LittleEndian>(124)?;
+    // caps, height, width and pixel format are set
+    header.write_u32::<LittleEndian>(0x1007)?;
+    header.write_u32::<LittleEndian>(height)?;
+    header.write_u32::<LittleEndian>(width)?;
+    // pitch, depth, mipmap count and reserved
+    header.extend_from_slice(&[0u8; 4 * 14]);
+    header.write_u32::<LittleEndian>(32)?;
+    // fourcc is set
+    header.write_u32::<LittleEndian>(0x4)?;
+    header.extend_from_slice(fourcc);
+    // bit count and masks
+    header.extend_from_slice(&[0u8; 4 * 5]);
+    // texture caps
+    header.write_u32::<LittleEndian>(0x1000)?;
+    // caps2, caps3, caps4 and reserved
+    header.extend_from_slice(&[0u8; 4 * 4]);
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::GenericImageView;
+
+    use super::*;
+
+    // a single dxt1 block where every pixel is the first color, white
+    const WHITE_DXT1: [u8; 8] = [0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    const GTF_DXT1_LINEAR: u8 = GTF_DXT1 | GTF_LINEAR;
+    const GTF_ARGB_LINEAR: u8 = GTF_A8R8G8B8 | GTF_LINEAR;
+
+    // one chunk stored as-is, which is how chunks that don't compress get written
+    fn stored(data: &[u8]) -> Vec<u8> {
+        let mut body = vec![0, 1, 0, 1];
+        body.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        body.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        body.extend_from_slice(data);
+        body
+    }
+
+    fn gtf(format: u8, width: u16, height: u16, data: &[u8]) -> Vec<u8> {
+        let mut res = b"GTF ".to_vec();
+        res.push(format);
+        res.extend_from_slice(&[0u8; 7]);
+        res.extend_from_slice(&width.to_be_bytes());
+        res.extend_from_slice(&height.to_be_bytes());
+        res.resize(4 + 0x18, 0);
+        res.extend_from_slice(&stored(data));
+        res
+    }
+
+    fn dds(width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+        let mut dds = dds_header(width, height, b"DXT1").unwrap();
+        dds.extend_from_slice(data);
+        dds
+    }
+
+    fn decode(res: &[u8]) -> Result<DynamicImage> {
+        let mut rdr = Cursor::new(res);
+        let info = ResourceInfo::parse(&mut rdr)?;
+        decode_gtf(&info, &mut rdr)
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("sacklite-texture-{}", Uuid::new_v4()))
+    }
+
+    #[test]
+    fn header() {
+        let header = dds_header(64, 32, b"DXT5").unwrap();
+        assert_eq!(header.len(), DDS_HEADER_SIZE);
+        assert_eq!(&header[..4], b"DDS ");
+        assert_eq!(LittleEndian::read_u32(&header[12..16]), 32);
+        assert_eq!(LittleEndian::read_u32(&header[16..20]), 64);
+        assert_eq!(&header[84..88], b"DXT5");
+    }
+
+    #[test]
+    fn dds_stops_after_top_level() {
+        let mut data = WHITE_DXT1.to_vec();
+        // a mipmap the decoder would never look at
+        data.extend_from_slice(&[0xAA; 32]);
+        let dds = read_dds(Cursor::new(dds(4, 4, &data))).unwrap();
+        assert_eq!(dds.len(), DDS_HEADER_SIZE + WHITE_DXT1.len());
+
+        let image = decode_limited(ImageReader::with_format(Cursor::new(dds), ImageFormat::Dds)).unwrap();
+        assert_eq!(image.dimensions(), (4, 4));
+        assert_eq!(image.get_pixel(3, 3).0, [0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn oversized_dds_is_rejected() {
+        assert!(read_dds(Cursor::new(dds(MAX_TEXTURE_SIZE * 2, 4, &WHITE_DXT1))).is_err());
+        assert!(read_dds(Cursor::new(dds(4, 0, &WHITE_DXT1))).is_err());
+    }
+
+    #[test]
+    fn invalid_dds() {
+        assert!(read_dds(Cursor::new(b"DDS ".to_vec())).is_err());
+        assert!(read_dds(Cursor::new(dds_header(4, 4, b"ATI2").unwrap())).is_err());
+        let mut not_dds = dds(4, 4, &WHITE_DXT1);
+        not_dds[..4].copy_from_slice(b"PNG ");
+        assert!(read_dds(Cursor::new(not_dds)).is_err());
+    }
+
+    #[test]
+    fn gtf_dxt() {
+        let image = decode(&gtf(GTF_DXT1_LINEAR, 4, 4, &WHITE_DXT1)).unwrap();
+        assert_eq!(image.dimensions(), (4, 4));
+        assert_eq!(image.get_pixel(0, 0).0, [0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn gtf_argb() {
+        let argb = [0xFF, 0x10, 0x20, 0x30, 0x80, 0x40, 0x50, 0x60];
+        let image = decode(&gtf(GTF_ARGB_LINEAR, 2, 1, &argb)).unwrap();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0).0, [0x10, 0x20, 0x30, 0xFF]);
+        assert_eq!(image.get_pixel(1, 0).0, [0x40, 0x50, 0x60, 0x80]);
+    }
+
+    #[test]
+    fn gtf_argb_with_wrong_size() {
+        assert!(decode(&gtf(GTF_ARGB_LINEAR, 2, 2, &[0; 8])).is_err());
+        assert!(decode(&gtf(GTF_ARGB_LINEAR, 1, 1, &[0; 8])).is_err());
+    }
+
+    #[test]
+    fn oversized_gtf_is_rejected() {
+        assert!(decode(&gtf(GTF_ARGB_LINEAR, 8192, 8192, &[0; 4])).is_err());
+        assert!(decode(&gtf(GTF_DXT1_LINEAR, 0, 4, &WHITE_DXT1)).is_err());
+    }
+
+    #[test]
+    fn unsupported_gtf_format() {
+        // swizzled argb
+        assert!(decode(&gtf(GTF_A8R8G8B8, 1, 1, &[0; 4])).is_err());
+    }
+
+    #[test]
+    fn png_is_cached() {
+        let resource_dir = temp_dir();
+        let cache_dir = temp_dir();
+        fs::create_dir_all(&resource_dir).unwrap();
+        let resource_dir_str = resource_dir.to_str().unwrap();
+        let cache_dir_str = cache_dir.to_str().unwrap();
+
+        let hash = [0x12; 20];
+        let mut tex = b"TEX ".to_vec();
+        tex.extend_from_slice(&stored(&dds(4, 4, &WHITE_DXT1)));
+        fs::write(get_hash_path(resource_dir_str, hash), tex).unwrap();
+
+        let path = get_png(resource_dir_str, cache_dir_str, hash).unwrap();
+        assert_eq!(image::open(&path).unwrap().dimensions(), (4, 4));
+
+        // once converted the resource isn't needed anymore
+        fs::remove_file(get_hash_path(resource_dir_str, hash)).unwrap();
+        assert_eq!(get_png(resource_dir_str, cache_dir_str, hash).unwrap(), path);
+        assert!(get_png(resource_dir_str, cache_dir_str, [0x34; 20]).is_err());
+
+        fs::remove_dir_all(resource_dir).unwrap();
+        fs::remove_dir_all(cache_dir).unwrap();
+    }
+}
\ No newline at end of file